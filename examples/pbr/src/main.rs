@@ -143,6 +143,7 @@ pub async fn run() {
                         NormalDistributionFunction::TrowbridgeReitzGGX,
                         GeometryFunction::SmithSchlickGGX,
                     ),
+                    ..model.material.clone()
                 };
                 model.render_with_material(&material, &camera, &[&light]);
                 gui.render()
@@ -32,6 +32,10 @@ mod scissor_box;
 #[doc(inline)]
 pub use scissor_box::*;
 
+mod query;
+#[doc(inline)]
+pub use query::*;
+
 pub mod prelude {
 
     //!
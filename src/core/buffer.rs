@@ -85,6 +85,8 @@ impl Buffer {
             );
             self.context.bind_buffer(crate::context::ARRAY_BUFFER, None);
         }
+        self.context
+            .update_frame_stats(|stats| stats.buffer_uploads += 1);
         self.attribute_count = data.len() as u32;
         self.data_type = T::data_type();
         self.data_size = T::size();
@@ -74,6 +74,8 @@ impl ElementBuffer {
             self.context
                 .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
         }
+        self.context
+            .update_frame_stats(|stats| stats.buffer_uploads += 1);
         self.count = data.len();
         self.data_type = T::data_type();
     }
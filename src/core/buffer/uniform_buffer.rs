@@ -108,6 +108,8 @@ impl UniformBuffer {
             self.context
                 .bind_buffer(crate::context::UNIFORM_BUFFER, None);
         }
+        self.context
+            .update_frame_stats(|stats| stats.buffer_uploads += 1);
     }
 }
 
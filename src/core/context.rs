@@ -17,8 +17,13 @@ pub struct Context {
     pub(super) vao: crate::context::VertexArray,
     /// A cache of programs to avoid recompiling a [Program] every frame.
     pub programs: Arc<RwLock<HashMap<Vec<u8>, Program>>>,
+    stats: Arc<RwLock<FrameStats>>,
+    bound_program: Arc<RwLock<Option<crate::context::Program>>>,
+    clip_control: Option<ClipControl>,
 }
 
+type ClipControl = unsafe extern "system" fn(u32, u32);
+
 impl Context {
     ///
     /// Creates a new mid-level context, used in this [core](crate::core) module, from a low-level OpenGL/WebGL context from the [context](crate::context) module.
@@ -44,11 +49,88 @@ impl Context {
                 context,
                 vao,
                 programs: Arc::new(RwLock::new(HashMap::new())),
+                stats: Arc::new(RwLock::new(FrameStats::default())),
+                bound_program: Arc::new(RwLock::new(None)),
+                clip_control: None,
             }
         };
         Ok(c)
     }
 
+    ///
+    /// Loads the `glClipControl` function using the given loader function, if it is supported by the graphics context,
+    /// which is the case for OpenGL 4.5 and contexts with the `GL_ARB_clip_control` or `GL_EXT_clip_control` extension.
+    /// Clip control makes it possible to use [DepthRange::ZeroToOne], see [Context::supports_clip_control].
+    /// This is done automatically by the contexts in the [window](crate::window) module,
+    /// but must be called with the same loader function as used for creating the low-level context if that is created otherwise.
+    ///
+    pub fn load_clip_control(&mut self, mut loader: impl FnMut(&str) -> *const std::ffi::c_void) {
+        let version = self.version();
+        let extensions = self.supported_extensions();
+        let name = if !version.is_embedded && (version.major, version.minor) >= (4, 5)
+            || extensions.contains("GL_ARB_clip_control")
+        {
+            "glClipControl"
+        } else if extensions.contains("GL_EXT_clip_control") {
+            "glClipControlEXT"
+        } else {
+            return;
+        };
+        let function = loader(name);
+        self.clip_control = if function.is_null() {
+            None
+        } else {
+            Some(unsafe { std::mem::transmute::<*const std::ffi::c_void, ClipControl>(function) })
+        };
+    }
+
+    ///
+    /// Returns whether or not clip control is available, ie. whether [DepthRange::ZeroToOne] can be used, see [Context::load_clip_control].
+    ///
+    pub fn supports_clip_control(&self) -> bool {
+        self.clip_control.is_some()
+    }
+
+    ///
+    /// Returns the statistics gathered since the last call to [Context::reset_frame_stats], for example the number of draw calls and triangles.
+    /// Call [Context::reset_frame_stats] at the beginning of each frame to get the statistics for a single frame.
+    ///
+    pub fn frame_stats(&self) -> FrameStats {
+        *self.stats.read().unwrap()
+    }
+
+    ///
+    /// Resets the statistics returned by [Context::frame_stats].
+    ///
+    pub fn reset_frame_stats(&self) {
+        *self.stats.write().unwrap() = FrameStats::default();
+    }
+
+    pub(super) fn update_frame_stats(&self, update: impl FnOnce(&mut FrameStats)) {
+        update(&mut self.stats.write().unwrap())
+    }
+
+    ///
+    /// Uses the given program for the following calls and counts a program bind in the [FrameStats] if it is not the program used last.
+    ///
+    pub(super) fn bind_program(&self, program: crate::context::Program) {
+        unsafe {
+            self.use_program(Some(program));
+        }
+        let mut bound_program = self.bound_program.write().unwrap();
+        self.update_frame_stats(|stats| stats.record_program_bind(&mut bound_program, program));
+    }
+
+    ///
+    /// Forgets the given program as the program used last, since the id can be reused by a new program after it is deleted.
+    ///
+    pub(super) fn forget_program(&self, program: crate::context::Program) {
+        let mut bound_program = self.bound_program.write().unwrap();
+        if *bound_program == Some(program) {
+            *bound_program = None;
+        }
+    }
+
     ///
     /// Set the scissor test for this context (see [ScissorBox]).
     ///
@@ -188,6 +270,37 @@ impl Context {
         }
     }
 
+    ///
+    /// Enables or disables alpha to coverage for this context (see [RenderStates::alpha_to_coverage]).
+    ///
+    pub fn set_alpha_to_coverage(&self, alpha_to_coverage: bool) {
+        unsafe {
+            if alpha_to_coverage {
+                self.enable(crate::context::SAMPLE_ALPHA_TO_COVERAGE);
+            } else {
+                self.disable(crate::context::SAMPLE_ALPHA_TO_COVERAGE);
+            }
+        }
+    }
+
+    ///
+    /// Set the depth range for this context (see [DepthRange]).
+    ///
+    /// # Panic
+    /// Will panic if the depth range is [DepthRange::ZeroToOne] and clip control is not supported, see [Context::supports_clip_control].
+    ///
+    pub fn set_depth_range(&self, depth_range: DepthRange) {
+        if let Some(clip_control) = self.clip_control {
+            let depth = match depth_range {
+                DepthRange::NegativeOneToOne => crate::context::NEGATIVE_ONE_TO_ONE,
+                DepthRange::ZeroToOne => crate::context::ZERO_TO_ONE,
+            };
+            unsafe { clip_control(crate::context::LOWER_LEFT, depth) }
+        } else if depth_range == DepthRange::ZeroToOne {
+            panic!("the depth range [0, 1] requires clip control which is not supported by this context");
+        }
+    }
+
     fn blend_const_from_multiplier(multiplier: BlendMultiplierType) -> u32 {
         match multiplier {
             BlendMultiplierType::Zero => crate::context::ZERO,
@@ -225,6 +338,8 @@ impl Context {
             self.set_depth_test(render_states.depth_test);
         }
         self.set_blend(render_states.blend);
+        self.set_alpha_to_coverage(render_states.alpha_to_coverage);
+        self.set_depth_range(render_states.depth_range);
     }
 
     ///
@@ -295,6 +410,46 @@ impl Context {
     }
 }
 
+///
+/// Statistics about the work done using a [Context], see [Context::frame_stats].
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// The number of draw calls.
+    pub draw_calls: u32,
+    /// The number of triangles drawn, including all instances.
+    pub triangles: u64,
+    /// The number of times the [Program] in use has changed,
+    /// so drawing several times in a row with the same program counts only one bind.
+    pub program_binds: u32,
+    /// The number of compiled programs.
+    pub program_compilations: u32,
+    /// The number of times texture memory has been allocated on or texture data has been uploaded to the GPU,
+    /// for example when creating a texture or render buffer, or using [Texture2D::fill].
+    /// Filling a [TextureCubeMap] counts one upload per side and filling a [Texture2DArray] counts one upload per layer.
+    pub texture_uploads: u32,
+    /// The number of times buffer data has been uploaded to the GPU, for example using [VertexBuffer::fill].
+    pub buffer_uploads: u32,
+}
+
+impl FrameStats {
+    pub(super) fn record_draw_call(&mut self, element_count: u32, instance_count: u32) {
+        self.draw_calls += 1;
+        self.triangles += element_count as u64 / 3 * instance_count as u64;
+    }
+
+    pub(super) fn record_program_bind<T: PartialEq>(
+        &mut self,
+        bound_program: &mut Option<T>,
+        program: T,
+    ) {
+        if bound_program.as_ref() != Some(&program) {
+            *bound_program = Some(program);
+            self.program_binds += 1;
+        }
+    }
+}
+
 impl std::fmt::Debug for Context {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut d = f.debug_struct("Context");
@@ -309,3 +464,48 @@ impl std::ops::Deref for Context {
         &self.context
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_stats_count_triangles_of_all_instances() {
+        let mut stats = FrameStats::default();
+        stats.record_draw_call(36, 1);
+        stats.record_draw_call(36, 10);
+        stats.record_draw_call(0, 5);
+        assert_eq!(stats.draw_calls, 3);
+        assert_eq!(stats.triangles, 12 + 120);
+    }
+
+    #[test]
+    fn frame_stats_triangles_do_not_overflow() {
+        let mut stats = FrameStats::default();
+        stats.record_draw_call(u32::MAX, u32::MAX);
+        assert_eq!(stats.triangles, (u32::MAX / 3) as u64 * u32::MAX as u64);
+    }
+
+    #[test]
+    fn frame_stats_count_program_binds_only_when_the_program_changes() {
+        let mut stats = FrameStats::default();
+        let mut bound_program = None;
+        // Two draws with the same program, where the program is used once for each uniform and once for the draw call
+        for _ in 0..2 {
+            for _ in 0..3 {
+                stats.record_program_bind(&mut bound_program, 1);
+            }
+        }
+        assert_eq!(stats.program_binds, 1);
+
+        // Two draws with different programs
+        let mut stats = FrameStats::default();
+        let mut bound_program = None;
+        for program in [1, 2] {
+            for _ in 0..3 {
+                stats.record_program_bind(&mut bound_program, program);
+            }
+        }
+        assert_eq!(stats.program_binds, 2);
+    }
+}
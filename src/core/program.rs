@@ -121,6 +121,7 @@ impl Program {
                 }
             }
 
+            context.update_frame_stats(|stats| stats.program_compilations += 1);
             Ok(Program {
                 context: context.clone(),
                 id,
@@ -431,6 +432,8 @@ impl Program {
         unsafe {
             self.context
                 .draw_arrays(crate::context::TRIANGLES, 0, count as i32);
+            self.context
+                .update_frame_stats(|stats| stats.record_draw_call(count, 1));
             for location in self.attributes.values() {
                 self.context.disable_vertex_attrib_array(*location);
             }
@@ -465,6 +468,8 @@ impl Program {
                 count as i32,
                 instance_count as i32,
             );
+            self.context
+                .update_frame_stats(|stats| stats.record_draw_call(count, instance_count));
             self.context
                 .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
             for location in self.attributes.values() {
@@ -524,6 +529,8 @@ impl Program {
                 element_buffer.data_type(),
                 first as i32,
             );
+            self.context
+                .update_frame_stats(|stats| stats.record_draw_call(count, 1));
             self.context
                 .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
 
@@ -586,6 +593,8 @@ impl Program {
                 first as i32,
                 instance_count as i32,
             );
+            self.context
+                .update_frame_stats(|stats| stats.record_draw_call(count, instance_count));
             self.context
                 .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
             for location in self.attributes.values() {
@@ -626,9 +635,7 @@ impl Program {
     }
 
     fn use_program(&self) {
-        unsafe {
-            self.context.use_program(Some(self.id));
-        }
+        self.context.bind_program(self.id);
     }
 
     fn unuse_program(&self) {
@@ -640,6 +647,7 @@ impl Program {
 
 impl Drop for Program {
     fn drop(&mut self) {
+        self.context.forget_program(self.id);
         unsafe {
             self.context.delete_program(self.id);
         }
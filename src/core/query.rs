@@ -0,0 +1,73 @@
+use crate::core::*;
+
+///
+/// An occlusion query which can be used to determine whether any samples of the draw calls issued between
+/// [OcclusionQuery::begin] and [OcclusionQuery::end] passed the depth test.
+/// The result is computed asynchronously on the GPU, so it is usually not available until a later frame, see [OcclusionQuery::result].
+///
+pub struct OcclusionQuery {
+    context: Context,
+    id: crate::context::Query,
+}
+
+impl OcclusionQuery {
+    ///
+    /// Creates a new occlusion query.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            id: unsafe { context.create_query().expect("Failed creating query") },
+        }
+    }
+
+    ///
+    /// Begins the query. All draw calls issued until [OcclusionQuery::end] is called are included in the query.
+    /// Must be called in the callback given as input to a [RenderTarget] or [DepthTarget] write method.
+    ///
+    pub fn begin(&self) {
+        unsafe {
+            self.context
+                .begin_query(crate::context::ANY_SAMPLES_PASSED, self.id);
+        }
+    }
+
+    ///
+    /// Ends the query.
+    ///
+    pub fn end(&self) {
+        unsafe {
+            self.context.end_query(crate::context::ANY_SAMPLES_PASSED);
+        }
+    }
+
+    ///
+    /// Returns whether any samples passed the depth test in the draw calls issued between [OcclusionQuery::begin] and [OcclusionQuery::end]
+    /// or `None` if the result is not available yet. This method never waits for the result.
+    ///
+    pub fn result(&self) -> Option<bool> {
+        unsafe {
+            if self
+                .context
+                .get_query_parameter_u32(self.id, crate::context::QUERY_RESULT_AVAILABLE)
+                != 0
+            {
+                Some(
+                    self.context
+                        .get_query_parameter_u32(self.id, crate::context::QUERY_RESULT)
+                        != 0,
+                )
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl Drop for OcclusionQuery {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.delete_query(self.id);
+        }
+    }
+}
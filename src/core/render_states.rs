@@ -31,6 +31,32 @@ pub struct RenderStates {
     /// Defines whether the triangles that are backfacing, frontfacing or both should be skipped in a render call.
     ///
     pub cull: Cull,
+
+    ///
+    /// Whether or not the alpha value of the output color is used to determine the coverage of each pixel, ie. how many of the samples in the pixel are written to.
+    /// This gives smooth edges of alpha masked surfaces, for example foliage, without sorting and blending.
+    /// Only has an effect when rendering into a multisampled render target, for example [RenderTargetMultisample](crate::core::RenderTargetMultisample).
+    ///
+    pub alpha_to_coverage: bool,
+
+    ///
+    /// Defines the range of the depth in normalized device coordinates which is mapped to the depth buffer in a render call.
+    /// This must match the projection used in the vertex shader.
+    ///
+    pub depth_range: DepthRange,
+}
+
+///
+/// Defines the range of the depth in normalized device coordinates which is mapped to the range `[0, 1]` of the depth buffer.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DepthRange {
+    /// The depth in the range `[-1, 1]` is mapped to the depth buffer, which is the default in OpenGL.
+    #[default]
+    NegativeOneToOne,
+    /// The depth in the range `[0, 1]` is mapped directly to the depth buffer, which avoids the loss of precision when mapping from `[-1, 1]`.
+    /// Requires clip control, see [Context::supports_clip_control](crate::core::Context::supports_clip_control).
+    ZeroToOne,
 }
 
 ///
@@ -201,6 +227,19 @@ impl Blend {
         rgb_equation: BlendEquationType::Add,
         alpha_equation: BlendEquationType::Add,
     };
+
+    ///
+    /// Adds the output color of the render call to the color of the render target multiplied with the output alpha value.
+    /// Used by transmissive materials, where the output alpha value is the amount of light transmitted from the render target.
+    ///
+    pub const TRANSMISSION: Self = Self::Enabled {
+        source_rgb_multiplier: BlendMultiplierType::One,
+        source_alpha_multiplier: BlendMultiplierType::Zero,
+        destination_rgb_multiplier: BlendMultiplierType::SrcAlpha,
+        destination_alpha_multiplier: BlendMultiplierType::One,
+        rgb_equation: BlendEquationType::Add,
+        alpha_equation: BlendEquationType::Add,
+    };
 }
 
 impl Default for Blend {
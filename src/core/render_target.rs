@@ -86,6 +86,14 @@ impl<'a> RenderTarget<'a> {
         self.height
     }
 
+    ///
+    /// Returns the number of samples for each fragment, which is 0 if this target is not multisampled.
+    ///
+    pub(crate) fn number_of_samples(&self) -> u32 {
+        self.bind(crate::context::DRAW_FRAMEBUFFER);
+        unsafe { self.context.get_parameter_i32(crate::context::SAMPLES) as u32 }
+    }
+
     ///
     /// Clears the color and depth of this render target as defined by the given clear state.
     ///
@@ -15,6 +15,7 @@ pub struct ClearState {
     /// Defines the clear value for the alpha channel.
     pub alpha: Option<f32>,
     /// Defines the clear value for the depth channel. A value of 1 means a depth value equal to the far plane and 0 means a depth value equal to the near plane.
+    /// This is reversed when rendering with a camera using reversed-Z, see [DepthMapping::clear_state](crate::renderer::DepthMapping::clear_state).
     pub depth: Option<f32>,
 }
 
@@ -107,6 +108,10 @@ impl ClearState {
 }
 
 impl Default for ClearState {
+    ///
+    /// Clears the color to opaque black and the depth to the far plane when using the standard depth mapping.
+    /// When rendering with a camera using reversed-Z, map the clear state using [DepthMapping::clear_state](crate::renderer::DepthMapping::clear_state).
+    ///
     fn default() -> Self {
         Self::color_and_depth(0.0, 0.0, 0.0, 1.0, 1.0)
     }
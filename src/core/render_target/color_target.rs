@@ -186,6 +186,13 @@ impl<'a> ColorTarget<'a> {
         }
     }
 
+    ///
+    /// Returns the number of samples for each fragment, which is 0 if this target is not multisampled.
+    ///
+    pub(crate) fn number_of_samples(&self) -> u32 {
+        self.as_render_target().number_of_samples()
+    }
+
     pub(super) fn as_render_target(&self) -> RenderTarget<'a> {
         RenderTarget::new_color(self.clone())
     }
@@ -151,6 +151,13 @@ impl<'a> DepthTarget<'a> {
         }
     }
 
+    ///
+    /// Returns the number of samples for each fragment, which is 0 if this target is not multisampled.
+    ///
+    pub(crate) fn number_of_samples(&self) -> u32 {
+        self.as_render_target().number_of_samples()
+    }
+
     pub(super) fn bind(&self) {
         if let Some(target) = &self.target {
             match target {
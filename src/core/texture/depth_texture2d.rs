@@ -48,6 +48,7 @@ impl DepthTexture2D {
                 height as i32,
             );
         }
+        context.update_frame_stats(|stats| stats.texture_uploads += 1);
         texture
     }
 
@@ -52,6 +52,7 @@ impl DepthTexture2DArray {
                 depth as i32,
             );
         }
+        context.update_frame_stats(|stats| stats.texture_uploads += 1);
         texture
     }
 
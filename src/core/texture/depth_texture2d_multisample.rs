@@ -38,6 +38,7 @@ impl DepthTexture2DMultisample {
                 height as i32,
             );
         }
+        context.update_frame_stats(|stats| stats.texture_uploads += 1);
         texture
     }
 
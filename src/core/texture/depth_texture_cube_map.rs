@@ -49,6 +49,7 @@ impl DepthTextureCubeMap {
                 height as i32,
             );
         }
+        context.update_frame_stats(|stats| stats.texture_uploads += 1);
         texture
     }
 
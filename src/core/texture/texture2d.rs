@@ -106,6 +106,7 @@ impl Texture2D {
                 height as i32,
             );
         }
+        context.update_frame_stats(|stats| stats.texture_uploads += 1);
         texture.generate_mip_maps();
         texture
     }
@@ -135,6 +136,8 @@ impl Texture2D {
                 crate::context::PixelUnpackData::Slice(to_byte_slice(&data)),
             );
         }
+        self.context
+            .update_frame_stats(|stats| stats.texture_uploads += 1);
         self.generate_mip_maps();
     }
 
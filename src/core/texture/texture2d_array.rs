@@ -197,6 +197,7 @@ impl Texture2DArray {
                 depth as i32,
             );
         }
+        context.update_frame_stats(|stats| stats.texture_uploads += 1);
         texture.generate_mip_maps();
         texture
     }
@@ -247,6 +248,8 @@ impl Texture2DArray {
                 crate::context::PixelUnpackData::Slice(to_byte_slice(&data)),
             );
         }
+        self.context
+            .update_frame_stats(|stats| stats.texture_uploads += 1);
         self.generate_mip_maps();
     }
 
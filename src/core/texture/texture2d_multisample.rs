@@ -37,6 +37,7 @@ impl Texture2DMultisample {
                 height as i32,
             );
         }
+        context.update_frame_stats(|stats| stats.texture_uploads += 1);
         texture
     }
 
@@ -110,6 +110,7 @@ impl Texture3D {
                 depth as i32,
             );
         }
+        context.update_frame_stats(|stats| stats.texture_uploads += 1);
         texture.generate_mip_maps();
         texture
     }
@@ -145,6 +146,8 @@ impl Texture3D {
                 crate::context::PixelUnpackData::Slice(to_byte_slice(data)),
             );
         }
+        self.context
+            .update_frame_stats(|stats| stats.texture_uploads += 1);
         self.generate_mip_maps();
     }
 
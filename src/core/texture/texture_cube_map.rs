@@ -349,6 +349,7 @@ impl TextureCubeMap {
                 height as i32,
             );
         }
+        context.update_frame_stats(|stats| stats.texture_uploads += 1);
         texture.generate_mip_maps();
         texture
     }
@@ -435,6 +436,8 @@ impl TextureCubeMap {
                     crate::context::PixelUnpackData::Slice(to_byte_slice(data)),
                 );
             }
+            self.context
+                .update_frame_stats(|stats| stats.texture_uploads += 1);
         }
         self.generate_mip_maps();
     }
@@ -29,6 +29,8 @@ pub enum RendererError {
     InvalidBufferLength(String, usize, usize),
     #[error("the material {0} is required by the geometry {1} but could not be found")]
     MissingMaterial(String, String),
+    #[error("failed parsing the color lookup table: {0}")]
+    InvalidColorLookupTable(String),
 }
 
 mod camera;
@@ -52,6 +54,15 @@ pub use object::*;
 pub mod control;
 pub use control::*;
 
+mod render_settings;
+pub use render_settings::*;
+
+mod occlusion_culler;
+pub use occlusion_culler::*;
+
+mod post_process;
+pub use post_process::*;
+
 macro_rules! impl_render_target_extensions_body {
     () => {
         ///
@@ -68,6 +79,28 @@ macro_rules! impl_render_target_extensions_body {
             self.render_partially(self.scissor_box(), camera, objects, lights)
         }
 
+        ///
+        /// Render the objects using the given camera and lights into this render target using the given [RenderSettings].
+        /// Use an empty array for the `lights` argument, if the objects does not require lights to be rendered.
+        /// Also, objects outside the camera frustum or found to be occluded by the [RenderSettings::occlusion_culler] are not rendered
+        /// and the objects are rendered in the order given by [RenderSettings::order].
+        ///
+        pub fn render_with_settings(
+            &self,
+            camera: &Camera,
+            objects: impl IntoIterator<Item = impl Object>,
+            lights: &[&dyn Light],
+            settings: RenderSettings,
+        ) -> &Self {
+            self.render_partially_with_settings(
+                self.scissor_box(),
+                camera,
+                objects,
+                lights,
+                settings,
+            )
+        }
+
         ///
         /// Render the objects using the given camera and lights into the part of this render target defined by the scissor box.
         /// Use an empty array for the `lights` argument, if the objects does not require lights to be rendered.
@@ -80,9 +113,286 @@ macro_rules! impl_render_target_extensions_body {
             objects: impl IntoIterator<Item = impl Object>,
             lights: &[&dyn Light],
         ) -> &Self {
+            self.render_partially_with_settings(
+                scissor_box,
+                camera,
+                objects,
+                lights,
+                RenderSettings::default(),
+            )
+        }
+
+        ///
+        /// Render the objects using the given camera and lights into the part of this render target defined by the scissor box using the given [RenderSettings].
+        /// Use an empty array for the `lights` argument, if the objects does not require lights to be rendered.
+        /// Also, objects outside the camera frustum or found to be occluded by the [RenderSettings::occlusion_culler] are not rendered
+        /// and the objects are rendered in the order given by [RenderSettings::order].
+        ///
+        pub fn render_partially_with_settings(
+            &self,
+            scissor_box: ScissorBox,
+            camera: &Camera,
+            objects: impl IntoIterator<Item = impl Object>,
+            lights: &[&dyn Light],
+            settings: RenderSettings,
+        ) -> &Self {
+            let sort = |objects: &mut Vec<_>, camera: &Camera| match settings.order {
+                RenderOrder::Distance => objects.sort_by(|a, b| cmp_render_order(camera, a, b)),
+                RenderOrder::State => sort_by_state(camera, objects),
+            };
+            let objects = objects
+                .into_iter()
+                .enumerate()
+                .filter(|(_, o)| camera.in_frustum(&o.aabb()))
+                .collect::<Vec<_>>();
+            let object_id = |i: usize| {
+                *settings.object_ids.get(i).expect(
+                    "The render settings must contain an id for each object when using an occlusion culler",
+                )
+            };
+            let mut aabbs = Vec::new();
+            if let Some(culler) = settings.occlusion_culler {
+                culler.update();
+                aabbs = objects
+                    .iter()
+                    .map(|(i, o)| (object_id(*i), o.aabb()))
+                    .collect();
+            }
+            let objects = objects
+                .into_iter()
+                .filter(|(i, _)| {
+                    settings
+                        .occlusion_culler
+                        .map_or(true, |culler| culler.is_visible(object_id(*i)))
+                })
+                .map(|(_, o)| o)
+                .collect::<Vec<_>>();
+
+            // Transmissive and order-independent transparency
+            let weighted_blended = settings.transparency == TransparencyMode::WeightedBlended
+                && objects
+                    .iter()
+                    .any(|o| o.material_type() == MaterialType::Transparent);
+            let transmissive = objects
+                .iter()
+                .any(|o| o.material_type() == MaterialType::Transmissive);
+            if weighted_blended || transmissive {
+                let (mut transparent_objects, objects): (Vec<_>, Vec<_>) = objects
+                    .into_iter()
+                    .partition(|o| o.material_type() == MaterialType::Transparent);
+                let (mut transmissive_objects, scene_objects): (Vec<_>, Vec<_>) = objects
+                    .into_iter()
+                    .partition(|o| o.material_type() == MaterialType::Transmissive);
+                let mut scene_camera = camera.clone();
+                let viewport =
+                    Viewport::new_at_origo(camera.viewport().width, camera.viewport().height);
+                scene_camera.set_viewport(viewport);
+                let scene_settings = RenderSettings {
+                    occlusion_culler: None,
+                    ..settings
+                };
+                let scene_clear_state = camera
+                    .depth_mapping
+                    .clear_state(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0));
+                let mut scene_depth_texture = DepthTexture2D::new::<f32>(
+                    &self.context,
+                    viewport.width,
+                    viewport.height,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                );
+                if transmissive {
+                    // Render the opaque scene into textures which are available to the transmissive objects,
+                    // using the same number of samples as this target before resolving it into the textures
+                    let mut scene_color_texture = Texture2D::new_empty::<[f16; 4]>(
+                        &self.context,
+                        viewport.width,
+                        viewport.height,
+                        Interpolation::Linear,
+                        Interpolation::Linear,
+                        Some(Interpolation::Linear),
+                        Wrapping::ClampToEdge,
+                        Wrapping::ClampToEdge,
+                    );
+                    let number_of_samples = self.number_of_samples();
+                    if number_of_samples > 0 {
+                        let scene_target = RenderTargetMultisample::<[f16; 4], f32>::new(
+                            &self.context,
+                            viewport.width,
+                            viewport.height,
+                            number_of_samples,
+                        );
+                        scene_target.clear(scene_clear_state).render_with_settings(
+                            &scene_camera,
+                            scene_objects,
+                            lights,
+                            scene_settings,
+                        );
+                        scene_target.resolve_color_to(&scene_color_texture.as_color_target(None));
+                        scene_target.resolve_depth_to(&scene_depth_texture.as_depth_target());
+                        scene_color_texture.generate_mip_maps();
+                    } else {
+                        RenderTarget::new(
+                            scene_color_texture.as_color_target(None),
+                            scene_depth_texture.as_depth_target(),
+                        )
+                        .clear(scene_clear_state)
+                        .render_with_settings(
+                            &scene_camera,
+                            scene_objects,
+                            lights,
+                            scene_settings,
+                        );
+                    }
+                    self.apply_screen_effect_partially(
+                        scissor_box,
+                        &SceneCopyEffect(CopyEffect {
+                            blend: Blend::TRANSPARENCY,
+                            ..Default::default()
+                        }),
+                        camera,
+                        &[],
+                        Some(ColorTexture::Single(&scene_color_texture)),
+                        Some(DepthTexture::Single(&scene_depth_texture)),
+                    );
+
+                    transmissive_objects.sort_by(|a, b| cmp_render_order(camera, a, b));
+                    self.write_partially::<RendererError>(scissor_box, || {
+                        for object in transmissive_objects.iter() {
+                            let rendered = with_material(object, |geometry, material| {
+                                geometry.render_with_material(
+                                    &TransmissionPassMaterial {
+                                        material,
+                                        color_texture: &scene_color_texture,
+                                        depth_texture: &scene_depth_texture,
+                                    },
+                                    camera,
+                                    lights,
+                                )
+                            });
+                            if rendered.is_none() {
+                                object.render(camera, lights);
+                            }
+                        }
+                        Ok(())
+                    })
+                    .unwrap();
+                } else {
+                    // The order-independent transparency only needs the depth of the opaque scene,
+                    // so render the depth into a texture and the opaque scene directly into this target
+                    let depth_material = DepthMaterial {
+                        render_states: RenderStates {
+                            write_mask: WriteMask::DEPTH,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    };
+                    scene_depth_texture
+                        .as_depth_target()
+                        .clear(scene_clear_state)
+                        .write::<RendererError>(|| {
+                            for object in scene_objects
+                                .iter()
+                                .filter(|o| scene_camera.in_frustum(&o.aabb()))
+                            {
+                                light::render_depth(
+                                    &self.context,
+                                    &scene_camera,
+                                    object,
+                                    &depth_material,
+                                );
+                            }
+                            Ok(())
+                        })
+                        .unwrap();
+                    self.render_partially_with_settings(
+                        scissor_box,
+                        camera,
+                        scene_objects,
+                        lights,
+                        scene_settings,
+                    );
+                }
+
+                if weighted_blended {
+                    // Accumulate the transparent objects into the accumulation and revealage textures
+                    let (blended_objects, unblended_objects): (Vec<_>, Vec<_>) =
+                        transparent_objects.into_iter().partition(|o| {
+                            with_material(o, |_, material| material.supports_weighted_blended())
+                                == Some(true)
+                        });
+                    transparent_objects = unblended_objects;
+                    let mut accumulation_texture = Texture2DArray::new_empty::<[f16; 4]>(
+                        &self.context,
+                        viewport.width,
+                        viewport.height,
+                        2,
+                        Interpolation::Nearest,
+                        Interpolation::Nearest,
+                        None,
+                        Wrapping::ClampToEdge,
+                        Wrapping::ClampToEdge,
+                    );
+                    let accumulation_layers = [0, 1];
+                    RenderTarget::new(
+                        accumulation_texture.as_color_target(&accumulation_layers, None),
+                        scene_depth_texture.as_depth_target(),
+                    )
+                    .clear(ClearState::color(0.0, 0.0, 0.0, 1.0))
+                    .write::<RendererError>(|| {
+                        let depth_material = DepthMaterial {
+                            render_states: RenderStates {
+                                write_mask: WriteMask::DEPTH,
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        };
+                        for object in transmissive_objects {
+                            object.render_with_material(&depth_material, &scene_camera, &[]);
+                        }
+                        for object in blended_objects {
+                            with_material(object, |geometry, material| {
+                                geometry.render_with_material(
+                                    &WeightedBlendedMaterial(material),
+                                    &scene_camera,
+                                    lights,
+                                )
+                            });
+                        }
+                        Ok(())
+                    })
+                    .unwrap();
+
+                    // Composite
+                    self.apply_screen_effect_partially(
+                        scissor_box,
+                        &WeightedBlendedCompositeEffect {},
+                        camera,
+                        &[],
+                        Some(ColorTexture::Array {
+                            texture: &accumulation_texture,
+                            layers: &accumulation_layers,
+                        }),
+                        None,
+                    );
+                }
+
+                transparent_objects.sort_by(|a, b| cmp_render_order(camera, a, b));
+                self.write_partially::<RendererError>(scissor_box, || {
+                    for object in transparent_objects {
+                        object.render(camera, lights);
+                    }
+                    if let Some(culler) = settings.occlusion_culler {
+                        culler.query(camera, &aabbs);
+                    }
+                    Ok(())
+                })
+                .unwrap();
+                return self;
+            }
+
             let (mut deferred_objects, mut forward_objects): (Vec<_>, Vec<_>) = objects
                 .into_iter()
-                .filter(|o| camera.in_frustum(&o.aabb()))
                 .partition(|o| o.material_type() == MaterialType::Deferred);
 
             // Deferred
@@ -92,12 +402,12 @@ macro_rules! impl_render_target_extensions_body {
                 let viewport =
                     Viewport::new_at_origo(camera.viewport().width, camera.viewport().height);
                 geometry_pass_camera.set_viewport(viewport);
-                deferred_objects.sort_by(|a, b| cmp_render_order(&geometry_pass_camera, a, b));
+                sort(&mut deferred_objects, &geometry_pass_camera);
                 let mut geometry_pass_texture = Texture2DArray::new_empty::<[u8; 4]>(
                     &self.context,
                     viewport.width,
                     viewport.height,
-                    3,
+                    4,
                     Interpolation::Nearest,
                     Interpolation::Nearest,
                     None,
@@ -111,12 +421,12 @@ macro_rules! impl_render_target_extensions_body {
                     Wrapping::ClampToEdge,
                     Wrapping::ClampToEdge,
                 );
-                let gbuffer_layers = [0, 1, 2];
+                let gbuffer_layers = [0, 1, 2, 3];
                 RenderTarget::new(
                     geometry_pass_texture.as_color_target(&gbuffer_layers, None),
                     geometry_pass_depth_texture.as_depth_target(),
                 )
-                .clear(ClearState::default())
+                .clear(camera.depth_mapping.clear_state(ClearState::default()))
                 .write::<RendererError>(|| {
                     for object in deferred_objects {
                         object.render(&geometry_pass_camera, lights);
@@ -140,10 +450,42 @@ macro_rules! impl_render_target_extensions_body {
             }
 
             // Forward
-            forward_objects.sort_by(|a, b| cmp_render_order(camera, a, b));
+            sort(&mut forward_objects, camera);
             self.write_partially::<RendererError>(scissor_box, || {
+                if settings.depth_pre_pass {
+                    for object in forward_objects.iter().filter(|o| is_depth_pre_passed(o)) {
+                        with_material(object, |geometry, material| {
+                            // Use the same culling as when shading, so that culled faces do not occlude the objects behind them
+                            let depth_material = DepthMaterial {
+                                render_states: RenderStates {
+                                    write_mask: WriteMask::DEPTH,
+                                    cull: material.render_states().cull,
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            };
+                            geometry.render_with_material(&depth_material, camera, &[])
+                        });
+                    }
+                }
                 for object in forward_objects {
-                    object.render(camera, lights);
+                    let rendered = if settings.depth_pre_pass && is_depth_pre_passed(&object) {
+                        with_material(&object, |geometry, material| {
+                            geometry.render_with_material(
+                                &DepthPrePassedMaterial(material),
+                                camera,
+                                lights,
+                            )
+                        })
+                    } else {
+                        None
+                    };
+                    if rendered.is_none() {
+                        object.render(camera, lights);
+                    }
+                }
+                if let Some(culler) = settings.occlusion_culler {
+                    culler.query(camera, &aabbs);
                 }
                 Ok(())
             })
@@ -199,6 +541,7 @@ macro_rules! impl_render_target_extensions_body {
         ///
         /// Render the geometries with the given [Effect] using the given camera and lights into this render target.
         /// Use an empty array for the `lights` argument, if the effect does not require lights to be rendered.
+        /// The effect is [preprocessed](Effect::preprocess) before rendering.
         ///
         pub fn render_with_effect(
             &self,
@@ -209,6 +552,7 @@ macro_rules! impl_render_target_extensions_body {
             color_texture: Option<ColorTexture>,
             depth_texture: Option<DepthTexture>,
         ) -> &Self {
+            effect.preprocess(&self.context, camera, lights, color_texture, depth_texture);
             self.render_partially_with_effect(
                 self.scissor_box(),
                 effect,
@@ -223,6 +567,7 @@ macro_rules! impl_render_target_extensions_body {
         ///
         /// Render the geometries with the given [Effect] using the given camera and lights into the part of this render target defined by the scissor box.
         /// Use an empty array for the `lights` argument, if the effect does not require lights to be rendered.
+        /// The effect is not [preprocessed](Effect::preprocess), so that it can be applied to several parts of the render target after preprocessing it once.
         ///
         pub fn render_partially_with_effect(
             &self,
@@ -290,6 +635,7 @@ macro_rules! impl_render_target_extensions_body {
         ///
         /// Apply the given [Effect] to this render target.
         /// Use an empty array for the `lights` argument, if the effect does not require lights to be rendered.
+        /// The effect is [preprocessed](Effect::preprocess) before it is applied.
         ///
         pub fn apply_screen_effect(
             &self,
@@ -299,6 +645,7 @@ macro_rules! impl_render_target_extensions_body {
             color_texture: Option<ColorTexture>,
             depth_texture: Option<DepthTexture>,
         ) -> &Self {
+            effect.preprocess(&self.context, camera, lights, color_texture, depth_texture);
             self.apply_screen_effect_partially(
                 self.scissor_box(),
                 effect,
@@ -312,6 +659,7 @@ macro_rules! impl_render_target_extensions_body {
         ///
         /// Apply the given [Effect] to the part of this render target defined by the scissor box.
         /// Use an empty array for the `lights` argument, if the effect does not require lights to be rendered.
+        /// The effect is not [preprocessed](Effect::preprocess), so that it can be applied to several parts of the render target after preprocessing it once.
         ///
         pub fn apply_screen_effect_partially(
             &self,
@@ -375,6 +723,267 @@ impl_render_target_extensions!(
 impl_render_target_extensions!(ColorTargetMultisample<C: TextureDataType>);
 impl_render_target_extensions!(DepthTargetMultisample<D: DepthTextureDataType>);
 
+fn is_depth_pre_passed(object: impl Object) -> bool {
+    object.material_type() == MaterialType::Opaque
+        && with_material(object, |_, material| !material.has_alpha_cutout()) == Some(true)
+}
+
+///
+/// Calls the given closure with the geometry and material of the object, see [Object::with_geometry_and_material],
+/// and returns the result or `None` if the object does not provide its geometry and material.
+///
+fn with_material<R>(
+    object: impl Object,
+    f: impl FnOnce(&dyn Geometry, &dyn Material) -> R,
+) -> Option<R> {
+    let mut f = Some(f);
+    let mut result = None;
+    object.with_geometry_and_material(&mut |geometry, material| {
+        if let Some(f) = f.take() {
+            result = Some(f(geometry, material));
+        }
+    });
+    result
+}
+
+///
+/// Wraps the material of an object whose depth has already been rendered in a depth pre-pass,
+/// so that the object is shaded without writing depth and only where it is the closest object.
+///
+struct DepthPrePassedMaterial<'a>(&'a dyn Material);
+
+impl Material for DepthPrePassedMaterial<'_> {
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        self.0.fragment_shader_source(lights)
+    }
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.0.fragment_attributes()
+    }
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.0.use_uniforms(program, camera, lights)
+    }
+    fn render_states(&self) -> RenderStates {
+        let render_states = self.0.render_states();
+        RenderStates {
+            depth_test: DepthTest::LessOrEqual,
+            write_mask: WriteMask {
+                depth: false,
+                ..render_states.write_mask
+            },
+            ..render_states
+        }
+    }
+    fn material_type(&self) -> MaterialType {
+        self.0.material_type()
+    }
+    fn id(&self) -> u16 {
+        self.0.id()
+    }
+    fn has_alpha_cutout(&self) -> bool {
+        self.0.has_alpha_cutout()
+    }
+    fn source_hash(&self) -> Option<u64> {
+        self.0.source_hash()
+    }
+}
+
+///
+/// Wraps the material of a transmissive object so that the textures containing the opaque scene are available in the fragment shader,
+/// see [MaterialType::Transmissive].
+///
+struct TransmissionPassMaterial<'a> {
+    material: &'a dyn Material,
+    color_texture: &'a Texture2D,
+    depth_texture: &'a DepthTexture2D,
+}
+
+impl Material for TransmissionPassMaterial<'_> {
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        self.material.fragment_shader_source(lights)
+    }
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.material.fragment_attributes()
+    }
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.material.use_uniforms(program, camera, lights);
+        if program.requires_uniform("sceneColorMap") {
+            program.use_texture("sceneColorMap", self.color_texture);
+        }
+        if program.requires_uniform("sceneDepthMap") {
+            program.use_depth_texture("sceneDepthMap", self.depth_texture);
+        }
+    }
+    fn render_states(&self) -> RenderStates {
+        self.material.render_states()
+    }
+    fn material_type(&self) -> MaterialType {
+        self.material.material_type()
+    }
+    fn id(&self) -> u16 {
+        self.material.id()
+    }
+    fn has_alpha_cutout(&self) -> bool {
+        self.material.has_alpha_cutout()
+    }
+    fn source_hash(&self) -> Option<u64> {
+        self.material.source_hash()
+    }
+}
+
+///
+/// Wraps the material of a transparent object so that the object is rendered into the accumulation and revealage textures
+/// used for weighted blended order-independent transparency, see [TransparencyMode::WeightedBlended].
+///
+struct WeightedBlendedMaterial<'a>(&'a dyn Material);
+
+impl Material for WeightedBlendedMaterial<'_> {
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        format!(
+            "#define WEIGHTED_BLENDED
+            layout (location = 1) out vec4 outWeight;
+            vec4 weighted_blended_output(vec4 color)
+            {{
+                float a = clamp(color.a, 0.0, 1.0);
+                float z = 1.0 / gl_FragCoord.w;
+                float w = a * clamp(10.0 / (0.00001 + pow(z / 5.0, 2.0) + pow(z / 200.0, 6.0)), 0.01, 3000.0);
+                outWeight = vec4(w, 0.0, 0.0, a);
+                return vec4(color.rgb * w, a);
+            }}
+            {}",
+            self.0.fragment_shader_source(lights)
+        )
+    }
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.0.fragment_attributes()
+    }
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.0.use_uniforms(program, camera, lights)
+    }
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            blend: Blend::Enabled {
+                source_rgb_multiplier: BlendMultiplierType::One,
+                source_alpha_multiplier: BlendMultiplierType::Zero,
+                destination_rgb_multiplier: BlendMultiplierType::One,
+                destination_alpha_multiplier: BlendMultiplierType::OneMinusSrcAlpha,
+                rgb_equation: BlendEquationType::Add,
+                alpha_equation: BlendEquationType::Add,
+            },
+            alpha_to_coverage: false,
+            ..self.0.render_states()
+        }
+    }
+    fn material_type(&self) -> MaterialType {
+        self.0.material_type()
+    }
+    fn id(&self) -> u16 {
+        self.0.id() | 0b1u16 << 13
+    }
+    fn source_hash(&self) -> Option<u64> {
+        self.0.source_hash()
+    }
+}
+
+///
+/// Composites the accumulation and revealage textures of weighted blended order-independent transparency onto a render target.
+///
+struct WeightedBlendedCompositeEffect {}
+
+impl Effect for WeightedBlendedCompositeEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}
+            in vec2 uvs;
+            layout (location = 0) out vec4 outColor;
+            void main()
+            {{
+                vec4 accumulation = sample_layer(uvs, 0);
+                float revealage = accumulation.a;
+                if (revealage > 0.9999) {{
+                    discard;
+                }}
+                float weight = sample_layer(uvs, 1).r;
+                outColor = vec4(accumulation.rgb / max(weight, 0.00001), 1.0 - revealage);
+            }}",
+            color_texture.unwrap().fragment_shader_source()
+        )
+    }
+    fn id(&self, color_texture: Option<ColorTexture>, _depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14 | 0b1u16 << 11 | color_texture.unwrap().id()
+    }
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        color_texture.unwrap().use_uniforms(program);
+    }
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTest::Always,
+            blend: Blend::TRANSPARENCY,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
+
+///
+/// Wraps an effect which copies the opaque scene to a render target, so that the copy is depth tested against what is already rendered.
+///
+struct SceneCopyEffect(CopyEffect);
+
+impl Effect for SceneCopyEffect {
+    fn fragment_shader_source(
+        &self,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        self.0
+            .fragment_shader_source(lights, color_texture, depth_texture)
+    }
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        self.0.id(color_texture, depth_texture)
+    }
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.0.fragment_attributes()
+    }
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        self.0
+            .use_uniforms(program, camera, lights, color_texture, depth_texture)
+    }
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::LessOrEqual,
+            ..self.0.render_states()
+        }
+    }
+}
+
 ///
 /// Render the given [Geometry] with the given [Material].
 /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
@@ -391,6 +1000,9 @@ pub fn render_with_material(
     let mut id = geometry.id(fragment_attributes).to_le_bytes().to_vec();
     id.extend(material.id().to_le_bytes());
     id.extend(lights.iter().map(|l| l.id()));
+    if let Some(hash) = material.source_hash() {
+        id.extend(hash.to_le_bytes());
+    }
 
     let mut programs = context.programs.write().unwrap();
     let program = programs.entry(id).or_insert_with(|| {
@@ -402,10 +1014,11 @@ pub fn render_with_material(
         .expect("Failed compiling shader")
     });
     material.use_uniforms(program, camera, lights);
+    camera.use_exposure_uniforms(program);
     geometry.draw(
         camera,
         program,
-        material.render_states(),
+        camera.depth_mapping.render_states(material.render_states()),
         fragment_attributes,
     );
 }
@@ -414,6 +1027,7 @@ pub fn render_with_material(
 /// Render the given [Geometry] with the given [Effect].
 /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
 /// Use an empty array for the `lights` argument, if the effect does not require lights to be rendered.
+/// The effect is not [preprocessed](Effect::preprocess), so call that before the write method, once per frame.
 ///
 pub fn render_with_effect(
     context: &Context,
@@ -439,7 +1053,13 @@ pub fn render_with_effect(
         .expect("Failed compiling shader")
     });
     effect.use_uniforms(program, camera, lights, color_texture, depth_texture);
-    geometry.draw(camera, program, effect.render_states(), fragment_attributes);
+    camera.use_exposure_uniforms(program);
+    geometry.draw(
+        camera,
+        program,
+        camera.depth_mapping.render_states(effect.render_states()),
+        fragment_attributes,
+    );
 }
 
 ///
@@ -460,6 +1080,9 @@ pub fn apply_screen_material(
     let mut id = (0b1u16 << 15).to_le_bytes().to_vec();
     id.extend(material.id().to_le_bytes());
     id.extend(lights.iter().map(|l| l.id()));
+    if let Some(hash) = material.source_hash() {
+        id.extend(hash.to_le_bytes());
+    }
 
     let mut programs = context.programs.write().unwrap();
     let program = programs.entry(id).or_insert_with(|| {
@@ -471,10 +1094,11 @@ pub fn apply_screen_material(
         .expect("Failed compiling shader")
     });
     material.use_uniforms(program, camera, lights);
+    camera.use_exposure_uniforms(program);
     full_screen_draw(
         context,
         program,
-        material.render_states(),
+        camera.depth_mapping.render_states(material.render_states()),
         camera.viewport(),
     );
 }
@@ -483,6 +1107,7 @@ pub fn apply_screen_material(
 /// Apply the given [Effect] to the entire sceen.
 /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
 /// Use an empty array for the `lights` argument, if the effect does not require lights to be rendered.
+/// The effect is not [preprocessed](Effect::preprocess), so call that before the write method, once per frame.
 ///
 pub fn apply_screen_effect(
     context: &Context,
@@ -510,33 +1135,39 @@ pub fn apply_screen_effect(
         .expect("Failed compiling shader")
     });
     effect.use_uniforms(program, camera, lights, color_texture, depth_texture);
-    full_screen_draw(context, program, effect.render_states(), camera.viewport());
+    camera.use_exposure_uniforms(program);
+    full_screen_draw(
+        context,
+        program,
+        camera.depth_mapping.render_states(effect.render_states()),
+        camera.viewport(),
+    );
 }
 
 ///
 /// Compare function for sorting objects based on distance from the camera.
 /// The order is opaque objects from nearest to farthest away from the camera,
-/// then transparent objects from farthest away to closest to the camera.
+/// then transmissive objects and finally transparent objects from farthest away to closest to the camera.
 ///
 pub fn cmp_render_order(
     camera: &Camera,
     obj0: impl Object,
     obj1: impl Object,
 ) -> std::cmp::Ordering {
-    if obj0.material_type() == MaterialType::Transparent
-        && obj1.material_type() != MaterialType::Transparent
-    {
-        std::cmp::Ordering::Greater
-    } else if obj0.material_type() != MaterialType::Transparent
-        && obj1.material_type() == MaterialType::Transparent
-    {
-        std::cmp::Ordering::Less
+    let rank = |material_type| match material_type {
+        MaterialType::Transmissive => 1,
+        MaterialType::Transparent => 2,
+        _ => 0,
+    };
+    let (rank0, rank1) = (rank(obj0.material_type()), rank(obj1.material_type()));
+    if rank0 != rank1 {
+        rank0.cmp(&rank1)
     } else {
         let distance_a = camera.position().distance2(obj0.aabb().center());
         let distance_b = camera.position().distance2(obj1.aabb().center());
         if distance_a.is_nan() || distance_b.is_nan() {
             distance_a.is_nan().cmp(&distance_b.is_nan()) // whatever - just save us from panicing on unwrap below
-        } else if obj0.material_type() == MaterialType::Transparent {
+        } else if rank0 > 0 {
             distance_b.partial_cmp(&distance_a).unwrap()
         } else {
             distance_a.partial_cmp(&distance_b).unwrap()
@@ -544,11 +1175,68 @@ pub fn cmp_render_order(
     }
 }
 
+///
+/// Compare function for sorting objects based on the state needed to render them and the distance from the camera.
+/// The order is opaque objects grouped by their [StateKey], ie. shader program, and within each group from nearest to farthest away from the camera,
+/// then transmissive objects and finally transparent objects from farthest away to closest to the camera.
+/// Opaque objects without a [StateKey] are rendered after the grouped opaque objects.
+///
+pub fn cmp_render_order_by_state(
+    camera: &Camera,
+    obj0: impl Object,
+    obj1: impl Object,
+) -> std::cmp::Ordering {
+    cmp_state_order_keys(
+        &state_order_key(camera, obj0),
+        &state_order_key(camera, obj1),
+    )
+}
+
+///
+/// Sorts the objects in the order given by [cmp_render_order_by_state], computing the state key and distance of each object only once.
+///
+fn sort_by_state<T: Object>(camera: &Camera, objects: &mut Vec<T>) {
+    let mut keyed = objects
+        .drain(..)
+        .map(|object| (state_order_key(camera, &object), object))
+        .collect::<Vec<_>>();
+    keyed.sort_by(|(key0, _), (key1, _)| cmp_state_order_keys(key0, key1));
+    objects.extend(keyed.into_iter().map(|(_, object)| object));
+}
+
+///
+/// The rank of the material type, whether the object has no state key, the state key and the signed squared distance to the camera,
+/// where the distance of transmissive and transparent objects is negated to render them from farthest away to closest to the camera.
+///
+type StateOrderKey = (u8, bool, Option<StateKey>, f32);
+
+fn state_order_key(camera: &Camera, object: impl Object) -> StateOrderKey {
+    let distance = camera.position().distance2(object.aabb().center());
+    match object.material_type() {
+        MaterialType::Transmissive => (1, false, None, -distance),
+        MaterialType::Transparent => (2, false, None, -distance),
+        _ => {
+            let state_key = object.state_key();
+            (0, state_key.is_none(), state_key, distance)
+        }
+    }
+}
+
+fn cmp_state_order_keys(key0: &StateOrderKey, key1: &StateOrderKey) -> std::cmp::Ordering {
+    key0.0
+        .cmp(&key1.0)
+        .then(key0.1.cmp(&key1.1))
+        .then(key0.2.cmp(&key1.2))
+        .then(key0.3.total_cmp(&key1.3))
+}
+
 ///
 /// Finds the closest intersection between a ray from the given camera in the given pixel coordinate and the given geometries.
 /// The pixel coordinate must be in physical pixels, where (viewport.x, viewport.y) indicate the bottom left corner of the viewport
 /// and (viewport.x + viewport.width, viewport.y + viewport.height) indicate the top right corner.
 /// Returns ```None``` if no geometry was hit between the near (`z_near`) and far (`z_far`) plane for this camera.
+/// If the camera uses [DepthMapping::ReversedZ] with an infinite far plane, geometries beyond `z_far` are also hit,
+/// except geometries with an infinite bounding box.
 ///
 pub fn pick(
     context: &Context,
@@ -558,11 +1246,26 @@ pub fn pick(
 ) -> Option<Vec3> {
     let pos = camera.position_at_pixel(pixel);
     let dir = camera.view_direction_at_pixel(pixel);
+    let geometries = geometries.into_iter().collect::<Vec<_>>();
+    let z_far = if let DepthMapping::ReversedZ {
+        infinite_far_plane: true,
+        ..
+    } = camera.depth_mapping
+    {
+        geometries
+            .iter()
+            .map(|geometry| geometry.aabb())
+            .filter(|aabb| !aabb.is_empty() && !aabb.is_infinite())
+            .map(|aabb| aabb.distance_max(&pos))
+            .fold(camera.z_far(), f32::max)
+    } else {
+        camera.z_far()
+    };
     ray_intersect(
         context,
         pos + dir * camera.z_near(),
         dir,
-        camera.z_far() - camera.z_near(),
+        z_far - camera.z_near(),
         geometries,
     )
 }
@@ -570,6 +1273,7 @@ pub fn pick(
 ///
 /// Finds the closest intersection between a ray starting at the given position in the given direction and the given geometries.
 /// Returns ```None``` if no geometry was hit before the given maximum depth.
+/// The depth is rendered using [DepthMapping::ReversedZ] if clip control is supported, see [Context::supports_clip_control].
 ///
 pub fn ray_intersect(
     context: &Context,
@@ -585,7 +1289,7 @@ pub fn ray_intersect(
     } else {
         direction.cross(vec3(1.0, 0.0, 0.0))
     };
-    let camera = Camera::new_orthographic(
+    let mut camera = Camera::new_orthographic(
         viewport,
         position,
         position + direction * max_depth,
@@ -594,6 +1298,7 @@ pub fn ray_intersect(
         0.0,
         max_depth,
     );
+    camera.depth_mapping = DepthMapping::highest_precision(context);
     let mut texture = Texture2D::new_empty::<f32>(
         context,
         viewport.width,
@@ -625,7 +1330,11 @@ pub fn ray_intersect(
         texture.as_color_target(None),
         depth_texture.as_depth_target(),
     )
-    .clear(ClearState::color_and_depth(1.0, 1.0, 1.0, 1.0, 1.0))
+    .clear(
+        camera
+            .depth_mapping
+            .clear_state(ClearState::color_and_depth(1.0, 1.0, 1.0, 1.0, 1.0)),
+    )
     .write::<RendererError>(|| {
         for geometry in geometries {
             render_with_material(context, &camera, &geometry, &depth_material, &[]);
@@ -640,3 +1349,138 @@ pub fn ray_intersect(
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestObject {
+        name: &'static str,
+        position: Vec3,
+        material_id: u16,
+        material_type: MaterialType,
+    }
+
+    impl Geometry for TestObject {
+        fn draw(&self, _: &Camera, _: &Program, _: RenderStates, _: FragmentAttributes) {
+            unimplemented!()
+        }
+
+        fn vertex_shader_source(&self, _required_attributes: FragmentAttributes) -> String {
+            unimplemented!()
+        }
+
+        fn id(&self, _required_attributes: FragmentAttributes) -> u16 {
+            0
+        }
+
+        fn render_with_material(&self, _: &dyn Material, _: &Camera, _: &[&dyn Light]) {
+            unimplemented!()
+        }
+
+        fn render_with_effect(
+            &self,
+            _: &dyn Effect,
+            _: &Camera,
+            _: &[&dyn Light],
+            _: Option<ColorTexture>,
+            _: Option<DepthTexture>,
+        ) {
+            unimplemented!()
+        }
+
+        fn aabb(&self) -> AxisAlignedBoundingBox {
+            AxisAlignedBoundingBox::new_with_positions(&[self.position])
+        }
+    }
+
+    impl Object for TestObject {
+        fn render(&self, _camera: &Camera, _lights: &[&dyn Light]) {
+            unimplemented!()
+        }
+
+        fn material_type(&self) -> MaterialType {
+            self.material_type
+        }
+
+        fn state_key(&self) -> Option<StateKey> {
+            Some(StateKey {
+                geometry_id: 0,
+                material_id: self.material_id,
+            })
+        }
+    }
+
+    fn object(name: &'static str, z: f32, material_id: u16) -> TestObject {
+        TestObject {
+            name,
+            position: vec3(0.0, 0.0, z),
+            material_id,
+            material_type: MaterialType::Opaque,
+        }
+    }
+
+    fn camera() -> Camera {
+        Camera::new_perspective(
+            Viewport::new_at_origo(100, 100),
+            vec3(0.0, 0.0, 10.0),
+            vec3(0.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            degrees(45.0),
+            0.1,
+            100.0,
+        )
+    }
+
+    fn names(objects: &[TestObject]) -> Vec<&'static str> {
+        objects.iter().map(|o| o.name).collect()
+    }
+
+    #[test]
+    fn state_order_is_front_to_back_within_a_program() {
+        let mut objects = vec![object("far", -5.0, 1), object("near", 5.0, 1)];
+        sort_by_state(&camera(), &mut objects);
+        assert_eq!(names(&objects), ["near", "far"]);
+
+        // The same order for references to the objects, ie. when the objects are not moved while sorting
+        let objects = [object("far", -5.0, 1), object("near", 5.0, 1)];
+        let mut references = objects.iter().collect::<Vec<_>>();
+        references.sort_by(|a, b| cmp_render_order_by_state(&camera(), a, b));
+        assert_eq!(
+            references.iter().map(|o| o.name).collect::<Vec<_>>(),
+            ["near", "far"]
+        );
+    }
+
+    #[test]
+    fn state_order_groups_by_program() {
+        let mut objects = vec![
+            object("far a", -5.0, 1),
+            object("near b", 5.0, 2),
+            object("near a", 4.0, 1),
+            object("far b", -4.0, 2),
+        ];
+        sort_by_state(&camera(), &mut objects);
+        assert_eq!(names(&objects), ["near a", "far a", "near b", "far b"]);
+    }
+
+    #[test]
+    fn state_order_renders_transparent_objects_last_from_back_to_front() {
+        let mut objects = vec![
+            TestObject {
+                material_type: MaterialType::Transparent,
+                ..object("near transparent", 5.0, 1)
+            },
+            TestObject {
+                material_type: MaterialType::Transparent,
+                ..object("far transparent", -5.0, 1)
+            },
+            object("opaque", 0.0, 3),
+        ];
+        sort_by_state(&camera(), &mut objects);
+        assert_eq!(
+            names(&objects),
+            ["opaque", "far transparent", "near transparent"]
+        );
+    }
+}
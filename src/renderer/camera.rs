@@ -4,6 +4,9 @@ pub use tone_mapping::*;
 mod color_space;
 pub use color_space::*;
 
+mod depth_mapping;
+pub use depth_mapping::*;
+
 use crate::core::*;
 
 ///
@@ -16,6 +19,17 @@ pub struct Camera {
     pub tone_mapping: ToneMapping,
     /// This color mapping is applied to the final color of renders using this camera.
     pub color_mapping: ColorMapping,
+    /// This depth mapping defines how depth is stored in the depth buffer when rendering with this camera.
+    /// Render targets must be cleared using [DepthMapping::clear_state] when using anything else than [DepthMapping::Standard].
+    pub depth_mapping: DepthMapping,
+    /// The exposure in stops (EV) which is applied to the color before the tone mapping, ie. the color is multiplied by `2^exposure`.
+    /// A positive value brightens and a negative value darkens the image.
+    /// The exposure is applied whatever the tone mapping is, except after a call to [Camera::disable_tone_and_color_mapping] so that it is not applied when rendering into intermediate render targets.
+    pub exposure: f32,
+    exposure_enabled: bool,
+    /// A sub-pixel offset in pixels applied to the projection, used to sample different positions within each pixel in consecutive frames, for example for temporal anti-aliasing.
+    pub jitter: Vec2,
+    previous_view_projection: Option<Mat4>,
 }
 
 impl Camera {
@@ -37,6 +51,11 @@ impl Camera {
             ),
             tone_mapping: ToneMapping::default(),
             color_mapping: ColorMapping::default(),
+            depth_mapping: DepthMapping::default(),
+            exposure: 0.0,
+            exposure_enabled: true,
+            jitter: vec2(0.0, 0.0),
+            previous_view_projection: None,
         }
     }
 
@@ -64,6 +83,11 @@ impl Camera {
             ),
             tone_mapping: ToneMapping::default(),
             color_mapping: ColorMapping::default(),
+            depth_mapping: DepthMapping::default(),
+            exposure: 0.0,
+            exposure_enabled: true,
+            jitter: vec2(0.0, 0.0),
+            previous_view_projection: None,
         }
     }
 
@@ -94,19 +118,154 @@ impl Camera {
     }
 
     ///
-    /// Disables the tone and color mapping so as to be ready for rendering into an intermediate render target with this camera.
+    /// Disables the tone and color mapping and the [Camera::exposure] so as to be ready for rendering into an intermediate render target with this camera.
     ///
     pub fn disable_tone_and_color_mapping(&mut self) {
         self.tone_mapping = ToneMapping::None;
         self.color_mapping = ColorMapping::None;
+        self.exposure_enabled = false;
     }
 
     ///
-    /// Sets the tone and color mapping to default so as to be ready for rendering into the final render target (usually the screen) with this camera.
+    /// Sets the tone and color mapping to default and enables the [Camera::exposure] so as to be ready for rendering into the final render target (usually the screen) with this camera.
     ///
     pub fn set_default_tone_and_color_mapping(&mut self) {
         self.tone_mapping = ToneMapping::default();
         self.color_mapping = ColorMapping::default();
+        self.exposure_enabled = true;
+    }
+
+    ///
+    /// Sends the uniform data needed to apply the [Camera::exposure] in the `tone_mapping` function, see [ToneMapping::fragment_shader_source].
+    ///
+    pub fn use_exposure_uniforms(&self, program: &Program) {
+        program.use_uniform_if_required("exposure", self.exposure);
+        program.use_uniform_if_required("applyExposure", self.exposure_enabled as i32);
+    }
+
+    ///
+    /// Returns the projection matrix, ie. the matrix that projects objects in view space onto this cameras image plane.
+    /// The projection takes the [DepthMapping] and the [Camera::jitter] into account.
+    ///
+    pub fn projection(&self) -> Mat4 {
+        let projection = self.projection_without_jitter();
+        if self.jitter == vec2(0.0, 0.0) {
+            projection
+        } else {
+            let viewport = self.viewport();
+            Mat4::from_translation(vec3(
+                2.0 * self.jitter.x / viewport.width as f32,
+                2.0 * self.jitter.y / viewport.height as f32,
+                0.0,
+            )) * projection
+        }
+    }
+
+    ///
+    /// Returns the projection matrix like [Camera::projection] but without the [Camera::jitter] applied.
+    ///
+    pub fn projection_without_jitter(&self) -> Mat4 {
+        let mut projection = *self.camera.projection();
+        if let DepthMapping::ReversedZ {
+            infinite_far_plane,
+            clip_control,
+        } = self.depth_mapping
+        {
+            if infinite_far_plane {
+                if let three_d_asset::ProjectionType::Perspective { .. } = self.projection_type() {
+                    projection[2][2] = -1.0;
+                    projection[3][2] = -2.0 * self.z_near();
+                }
+            }
+            for column in 0..4 {
+                projection[column][2] = if clip_control {
+                    // Maps the depth from [-1, 1] to [1, 0] in normalized device coordinates
+                    0.5 * (projection[column][3] - projection[column][2])
+                } else {
+                    -projection[column][2]
+                };
+            }
+        }
+        projection
+    }
+
+    ///
+    /// Returns the projection matrix like [Camera::projection] but with the depth in normalized device coordinates always in the range `[-1, 1]`,
+    /// whatever the [DepthRange] used by the [DepthMapping]. The depth in normalized device coordinates is therefore `2 * depth - 1`, where `depth` is the value in the depth buffer.
+    /// Use this when reconstructing positions from the depth buffer or when comparing a projected position with the value in the depth buffer.
+    ///
+    pub fn depth_reconstruction_projection(&self) -> Mat4 {
+        let projection = self.projection();
+        if let DepthMapping::ReversedZ {
+            clip_control: true, ..
+        } = self.depth_mapping
+        {
+            // Maps the depth from [0, 1] to [-1, 1] in normalized device coordinates
+            let mut remap = Mat4::identity();
+            remap[2][2] = 2.0;
+            remap[3][2] = -1.0;
+            remap * projection
+        } else {
+            projection
+        }
+    }
+
+    ///
+    /// Returns the view-projection matrix, without the [Camera::jitter] applied, stored at the last call to [Camera::update_previous_view_projection]
+    /// or the current view-projection matrix if it has never been called.
+    /// This is used to calculate the motion of the rendered objects between two frames, see [FragmentAttributes::velocity](crate::renderer::FragmentAttributes::velocity).
+    ///
+    pub fn previous_view_projection(&self) -> Mat4 {
+        self.previous_view_projection
+            .unwrap_or_else(|| self.projection_without_jitter() * self.view())
+    }
+
+    ///
+    /// Stores the current view-projection matrix as the view-projection matrix of the previous frame.
+    /// Call this once each frame before the camera is moved.
+    ///
+    pub fn update_previous_view_projection(&mut self) {
+        self.previous_view_projection = Some(self.projection_without_jitter() * self.view());
+    }
+
+    ///
+    /// Returns whether or not the given bounding box is within the camera frustum.
+    /// It returns false if it is fully outside and true if it is inside or intersects.
+    /// The far plane is ignored when using [DepthMapping::ReversedZ] with an infinite far plane.
+    ///
+    pub fn in_frustum(&self, aabb: &AxisAlignedBoundingBox) -> bool {
+        if let DepthMapping::ReversedZ {
+            infinite_far_plane: true,
+            ..
+        } = self.depth_mapping
+        {
+            if aabb.is_infinite() {
+                return true;
+            }
+            let m = self.projection() * self.view();
+            // The planes of the frustum except the far plane
+            let planes = [
+                m.row(3) + m.row(0),
+                m.row(3) - m.row(0),
+                m.row(3) + m.row(1),
+                m.row(3) - m.row(1),
+                m.row(3) - m.row(2),
+            ];
+            let (min, max) = (aabb.min(), aabb.max());
+            !planes.iter().any(|plane| {
+                (0..8).all(|i| {
+                    let corner = vec4(
+                        if i & 1 == 0 { min.x } else { max.x },
+                        if i & 2 == 0 { min.y } else { max.y },
+                        if i & 4 == 0 { min.z } else { max.z },
+                        1.0,
+                    );
+                    plane.dot(corner) < 0.0
+                })
+            })
+        } else {
+            self.camera.in_frustum(aabb)
+        }
     }
 }
 
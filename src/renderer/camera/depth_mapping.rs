@@ -0,0 +1,109 @@
+use crate::core::*;
+
+///
+/// Defines how the distance from the camera is mapped to the values in the depth buffer.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DepthMapping {
+    /// The near plane is mapped to a depth of 0 and the far plane to a depth of 1.
+    #[default]
+    Standard,
+    /// Reversed-Z, ie. the near plane is mapped to a depth of 1 and the far plane (or infinity) to a depth of 0, which reduces z-fighting at a distance when used together with a floating point depth buffer.
+    /// All depth tests are reversed, for example [DepthTest::Less] becomes [DepthTest::Greater], and the depth must be cleared to 0 instead of 1,
+    /// so always clear render targets using [DepthMapping::clear_state], since nothing is rendered when clearing with [ClearState::default].
+    /// Construct it using [DepthMapping::reversed_z] to use clip control when it is supported by the context.
+    ReversedZ {
+        /// Whether or not to use a projection with an infinite far plane instead of the far plane of the camera.
+        /// Only applicable for perspective projections.
+        infinite_far_plane: bool,
+        /// Whether or not the depth is mapped from the range `[0, 1]` in normalized device coordinates using clip control, see [DepthRange::ZeroToOne].
+        /// Without clip control, the graphics API maps the depth from `[-1, 1]` to `[0, 1]`, which cancels most of the precision gained by reversing the depth.
+        clip_control: bool,
+    },
+}
+
+impl DepthMapping {
+    ///
+    /// Returns [DepthMapping::ReversedZ] using clip control if it is supported by the given context, see [Context::supports_clip_control].
+    ///
+    pub fn reversed_z(context: &Context, infinite_far_plane: bool) -> Self {
+        Self::ReversedZ {
+            infinite_far_plane,
+            clip_control: context.supports_clip_control(),
+        }
+    }
+
+    ///
+    /// Returns the depth mapping with the highest precision supported by the given context,
+    /// ie. [DepthMapping::ReversedZ] if clip control is supported and otherwise [DepthMapping::Standard].
+    ///
+    pub(crate) fn highest_precision(context: &Context) -> Self {
+        if context.supports_clip_control() {
+            Self::reversed_z(context, false)
+        } else {
+            Self::Standard
+        }
+    }
+
+    ///
+    /// Returns the value in the depth buffer at the far plane.
+    ///
+    pub fn far_depth(&self) -> f32 {
+        match self {
+            Self::Standard => 1.0,
+            Self::ReversedZ { .. } => 0.0,
+        }
+    }
+
+    ///
+    /// Returns the depth in normalized device coordinates at the far plane.
+    ///
+    pub(crate) fn far_ndc_depth(&self) -> f32 {
+        match self {
+            Self::Standard => 1.0,
+            Self::ReversedZ {
+                clip_control: true, ..
+            } => 0.0,
+            Self::ReversedZ { .. } => -1.0,
+        }
+    }
+
+    ///
+    /// Returns the given clear state with the depth value mapped by this depth mapping.
+    /// The input depth value should be given as for [DepthMapping::Standard], ie. 1 means the far plane.
+    ///
+    pub fn clear_state(&self, clear_state: ClearState) -> ClearState {
+        match self {
+            Self::Standard => clear_state,
+            Self::ReversedZ { .. } => ClearState {
+                depth: clear_state.depth.map(|depth| 1.0 - depth),
+                ..clear_state
+            },
+        }
+    }
+
+    ///
+    /// Returns the given render states with the depth test mapped by this depth mapping.
+    /// The input depth test should be given as for [DepthMapping::Standard], ie. [DepthTest::Less] means that closer fragments pass the test.
+    ///
+    pub fn render_states(&self, render_states: RenderStates) -> RenderStates {
+        match *self {
+            Self::Standard => render_states,
+            Self::ReversedZ { clip_control, .. } => RenderStates {
+                depth_test: match render_states.depth_test {
+                    DepthTest::Less => DepthTest::Greater,
+                    DepthTest::LessOrEqual => DepthTest::GreaterOrEqual,
+                    DepthTest::Greater => DepthTest::Less,
+                    DepthTest::GreaterOrEqual => DepthTest::LessOrEqual,
+                    depth_test => depth_test,
+                },
+                depth_range: if clip_control {
+                    DepthRange::ZeroToOne
+                } else {
+                    DepthRange::NegativeOneToOne
+                },
+                ..render_states
+            },
+        }
+    }
+}
@@ -3,6 +3,7 @@ use crate::core::*;
 ///
 /// Tone mapping is the process of mapping HDR color values computed with physical based rendering in the range `[0,∞)`
 /// into LDR values that can be displayed on the screen in the range `[0,1]`.
+/// Before the tone mapping is applied, the color is scaled by the [Camera::exposure](crate::renderer::Camera::exposure), also when the tone mapping is [ToneMapping::None].
 ///
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
 pub enum ToneMapping {
@@ -24,8 +25,13 @@ impl ToneMapping {
     pub fn fragment_shader_source() -> &'static str {
         "
         uniform uint toneMappingType;
+        uniform float exposure;
+        uniform int applyExposure;
 
         vec3 tone_mapping(vec3 color) {
+            if (applyExposure != 0) {
+                color *= exp2(exposure);
+            }
             if (toneMappingType == 1u) {
                 color = color / (color + vec3(1.0));
                 color = clamp(color, 0.0, 1.0);
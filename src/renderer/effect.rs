@@ -41,6 +41,18 @@ macro_rules! impl_effect_body {
         fn render_states(&self) -> RenderStates {
             self.$inner().render_states()
         }
+
+        fn preprocess(
+            &self,
+            context: &Context,
+            camera: &Camera,
+            lights: &[&dyn Light],
+            color_texture: Option<ColorTexture>,
+            depth_texture: Option<DepthTexture>,
+        ) {
+            self.$inner()
+                .preprocess(context, camera, lights, color_texture, depth_texture)
+        }
     };
 }
 
@@ -64,6 +76,46 @@ mod water;
 #[doc(inline)]
 pub use water::*;
 
+mod toon_outline;
+#[doc(inline)]
+pub use toon_outline::*;
+
+mod bloom;
+#[doc(inline)]
+pub use bloom::*;
+
+mod ssr;
+#[doc(inline)]
+pub use ssr::*;
+
+mod depth_of_field;
+#[doc(inline)]
+pub use depth_of_field::*;
+
+mod taa;
+#[doc(inline)]
+pub use taa::*;
+
+mod motion_blur;
+#[doc(inline)]
+pub use motion_blur::*;
+
+mod auto_exposure;
+#[doc(inline)]
+pub use auto_exposure::*;
+
+mod color_grading;
+#[doc(inline)]
+pub use color_grading::*;
+
+mod selection_outline;
+#[doc(inline)]
+pub use selection_outline::*;
+
+mod edge_detection;
+#[doc(inline)]
+pub use edge_detection::*;
+
 pub(crate) mod lighting_pass;
 
 use crate::renderer::*;
@@ -114,6 +166,26 @@ pub trait Effect {
     /// Returns the render states needed to render with this effect.
     ///
     fn render_states(&self) -> RenderStates;
+
+    ///
+    /// Prepares the effect before it is applied, for example by rendering intermediate results into textures owned by the effect.
+    /// This is called by [RenderTarget::apply_screen_effect] and [RenderTarget::render_with_effect] before the render target is bound,
+    /// so it is possible to render into other render targets here. The default implementation does nothing.
+    ///
+    /// It is not called by the partial variants of these methods, [Geometry::render_with_effect] or the free functions [render_with_effect] and [apply_screen_effect],
+    /// so when using those, call this once per frame before the write method of the render target, since effects that keep a history,
+    /// for example the [AutoExposureEffect], advance the history each time this is called.
+    /// Effects that need to be preprocessed panic if they are applied without having been preprocessed.
+    ///
+    fn preprocess(
+        &self,
+        _context: &Context,
+        _camera: &Camera,
+        _lights: &[&dyn Light],
+        _color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+    }
 }
 
 impl<T: Effect + ?Sized> Effect for &T {
@@ -176,4 +248,150 @@ impl<T: Effect> Effect for std::sync::RwLock<T> {
     fn render_states(&self) -> RenderStates {
         self.read().unwrap().render_states()
     }
+
+    fn preprocess(
+        &self,
+        context: &Context,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        self.read()
+            .unwrap()
+            .preprocess(context, camera, lights, color_texture, depth_texture)
+    }
+}
+
+///
+/// The velocity used by the [TemporalAntiAliasingEffect] and the [MotionBlurEffect].
+/// The velocity is either read from the second layer of the color texture, if it is a texture array with at least two layers,
+/// or from a texture owned by the effect which is rendered using [VelocityTexture::render].
+///
+#[derive(Default)]
+struct VelocityTexture {
+    textures: std::sync::RwLock<Option<(Texture2D, DepthTexture2D)>>,
+}
+
+impl VelocityTexture {
+    ///
+    /// Renders the velocity of the given geometries, using the [VelocityMaterial], into the texture owned by this struct.
+    ///
+    fn render(
+        &self,
+        context: &Context,
+        camera: &Camera,
+        geometries: impl IntoIterator<Item = impl Geometry>,
+    ) {
+        let viewport = camera.viewport();
+        let mut textures = self.textures.write().unwrap();
+        if textures
+            .as_ref()
+            .map(|(t, _)| t.width() != viewport.width || t.height() != viewport.height)
+            .unwrap_or(true)
+        {
+            *textures = Some((
+                Texture2D::new_empty::<[f16; 2]>(
+                    context,
+                    viewport.width,
+                    viewport.height,
+                    Interpolation::Nearest,
+                    Interpolation::Nearest,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+                DepthTexture2D::new::<f32>(
+                    context,
+                    viewport.width,
+                    viewport.height,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+            ));
+        }
+        let (texture, depth_texture) = textures.as_mut().unwrap();
+        let mut velocity_camera = camera.clone();
+        velocity_camera.set_viewport(Viewport::new_at_origo(viewport.width, viewport.height));
+        RenderTarget::new(
+            texture.as_color_target(None),
+            depth_texture.as_depth_target(),
+        )
+        .clear(
+            camera
+                .depth_mapping
+                .clear_state(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0)),
+        )
+        .write::<RendererError>(|| {
+            for geometry in geometries
+                .into_iter()
+                .filter(|g| velocity_camera.in_frustum(&g.aabb()))
+            {
+                render_with_material(
+                    context,
+                    &velocity_camera,
+                    &geometry,
+                    VelocityMaterial::default(),
+                    &[],
+                );
+            }
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    ///
+    /// Returns whether or not the velocity is read from the second layer of the given color texture.
+    ///
+    fn is_layer(color_texture: Option<ColorTexture>, effect: &str) -> bool {
+        match color_texture {
+            Some(ColorTexture::Array { layers, .. }) if layers.len() >= 2 => true,
+            Some(_) => false,
+            None => panic!("Must supply a color texture to apply a {} effect", effect),
+        }
+    }
+
+    ///
+    /// Returns the fragment shader source defining the function `vec2 sample_velocity(vec2 uv)`.
+    ///
+    fn fragment_shader_source(color_texture: Option<ColorTexture>, effect: &str) -> String {
+        if Self::is_layer(color_texture, effect) {
+            "vec2 sample_velocity(vec2 uv)
+            {
+                return sample_layer(uv, 1).xy;
+            }"
+        } else {
+            "uniform sampler2D velocityMap;
+            vec2 sample_velocity(vec2 uv)
+            {
+                return texture(velocityMap, uv).xy;
+            }"
+        }
+        .to_owned()
+    }
+
+    ///
+    /// Returns a unique ID for each variation of the shader source returned from [VelocityTexture::fragment_shader_source].
+    ///
+    fn id(color_texture: Option<ColorTexture>, effect: &str) -> u16 {
+        if Self::is_layer(color_texture, effect) {
+            0
+        } else {
+            0b1u16 << 2
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, color_texture: Option<ColorTexture>, effect: &str) {
+        if !Self::is_layer(color_texture, effect) {
+            let textures = self.textures.read().unwrap();
+            let (texture, _) = textures.as_ref().unwrap_or_else(|| {
+                panic!(
+                    "The velocity must be rendered using the render_velocity method of the {} effect, \
+                    unless the color texture is a texture array with the velocity in the second layer",
+                    effect
+                )
+            });
+            program.use_texture("velocityMap", texture);
+        }
+    }
 }
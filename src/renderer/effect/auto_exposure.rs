@@ -0,0 +1,322 @@
+use crate::renderer::*;
+use std::sync::RwLock;
+
+///
+/// An effect that automatically adjusts the exposure to the brightness of the scene, similar to how the eye adapts when going from a dark to a bright environment.
+///
+/// Each frame, the average log luminance of the scene color is calculated by rendering it into a small texture and reducing it using the mip maps of that texture.
+/// The exposure is then adapted towards the exposure that maps the average luminance to middle gray over time using the [AutoExposureEffect::adaptation_speed]
+/// and the color is scaled by the adapted exposure before the tone and color mapping specified in the [Camera] is applied.
+/// Therefore, the effect should be given the scene color in linear HDR color space, for example rendered using a [PostProcessStack].
+/// Since the effect keeps the adapted luminance, the same instance must be applied each frame.
+///
+pub struct AutoExposureEffect {
+    /// An exposure compensation in stops (EV) which is added to the automatically calculated exposure.
+    pub compensation: f32,
+    /// The lowest average luminance which the exposure adapts to, ie. limits how much dark scenes are brightened.
+    pub min_luminance: f32,
+    /// The highest average luminance which the exposure adapts to, ie. limits how much bright scenes are darkened.
+    pub max_luminance: f32,
+    /// How fast the exposure adapts to changes in the brightness of the scene. A higher value gives a faster adaptation.
+    pub adaptation_speed: f32,
+    /// The time in milliseconds since the previous frame, used for adapting the exposure independently of the frame rate.
+    pub elapsed_time: f32,
+    textures: RwLock<Option<AutoExposureTextures>>,
+}
+
+struct AutoExposureTextures {
+    luminance: Texture2D,
+    adapted: Vec<Texture2D>,
+    has_history: bool,
+}
+
+const LUMINANCE_TEXTURE_SIZE: u32 = 256;
+
+impl Default for AutoExposureEffect {
+    fn default() -> Self {
+        Self {
+            compensation: 0.0,
+            min_luminance: 0.03,
+            max_luminance: 10.0,
+            adaptation_speed: 1.5,
+            elapsed_time: 16.0,
+            textures: RwLock::new(None),
+        }
+    }
+}
+
+impl AutoExposureEffect {
+    ///
+    /// Discards the adapted luminance, so that the exposure is immediately set to the brightness of the next frame, for example when switching scenes.
+    ///
+    pub fn reset(&self) {
+        if let Some(textures) = self.textures.write().unwrap().as_mut() {
+            textures.has_history = false;
+        }
+    }
+}
+
+impl Effect for AutoExposureEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}",
+            color_texture
+                .expect("Must supply a color texture to apply an auto exposure effect")
+                .fragment_shader_source(),
+            depth_texture
+                .map(|t| format!("#define USE_DEPTH\n{}", t.fragment_shader_source()))
+                .unwrap_or_default(),
+            ToneMapping::fragment_shader_source(),
+            ColorMapping::fragment_shader_source(),
+            include_str!("shaders/auto_exposure_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 12
+            | 0b1u16 << 7
+            | color_texture
+                .expect("Must supply a color texture to apply an auto exposure effect")
+                .id()
+            | depth_texture.map(|t| t.id()).unwrap_or(0u16)
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.color_mapping.use_uniforms(program);
+        color_texture
+            .expect("Must supply a color texture to apply an auto exposure effect")
+            .use_uniforms(program);
+        if let Some(depth_texture) = depth_texture {
+            depth_texture.use_uniforms(program);
+        }
+        program.use_texture(
+            "adaptedLuminanceMap",
+            self.textures
+                .read()
+                .unwrap()
+                .as_ref()
+                .and_then(|t| t.adapted.first())
+                .expect("The auto exposure effect must be preprocessed before it is applied"),
+        );
+        program.use_uniform("compensation", self.compensation);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+
+    fn preprocess(
+        &self,
+        context: &Context,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        let color_texture =
+            color_texture.expect("Must supply a color texture to apply an auto exposure effect");
+        let mut textures = self.textures.write().unwrap();
+        let textures = textures.get_or_insert_with(|| AutoExposureTextures {
+            luminance: Texture2D::new_empty::<f16>(
+                context,
+                LUMINANCE_TEXTURE_SIZE,
+                LUMINANCE_TEXTURE_SIZE,
+                Interpolation::Linear,
+                Interpolation::Linear,
+                Some(Interpolation::Linear),
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+            ),
+            adapted: (0..2)
+                .map(|_| {
+                    Texture2D::new_empty::<f16>(
+                        context,
+                        1,
+                        1,
+                        Interpolation::Nearest,
+                        Interpolation::Nearest,
+                        None,
+                        Wrapping::ClampToEdge,
+                        Wrapping::ClampToEdge,
+                    )
+                })
+                .collect(),
+            has_history: false,
+        });
+        let mut pass_camera = camera.clone();
+        pass_camera.disable_tone_and_color_mapping();
+
+        // Calculate the log luminance, the average is found in the smallest mip level which is generated after writing
+        pass_camera.set_viewport(Viewport::new_at_origo(
+            LUMINANCE_TEXTURE_SIZE,
+            LUMINANCE_TEXTURE_SIZE,
+        ));
+        textures
+            .luminance
+            .as_color_target(None)
+            .apply_screen_effect(
+                &LuminancePass {},
+                &pass_camera,
+                &[],
+                Some(color_texture),
+                None,
+            );
+
+        // Adapt the luminance of the previous frame towards the average luminance of this frame
+        let blend_factor = if textures.has_history {
+            1.0 - (-self.adaptation_speed.max(0.0) * 0.001 * self.elapsed_time.max(0.0)).exp()
+        } else {
+            1.0
+        };
+        pass_camera.set_viewport(Viewport::new_at_origo(1, 1));
+        let (previous, adapted) = textures.adapted.split_at_mut(1);
+        adapted[0].as_color_target(None).apply_screen_effect(
+            &AdaptationPass {
+                luminance: &textures.luminance,
+                previous: &previous[0],
+                blend_factor,
+                min_luminance: self.min_luminance,
+                max_luminance: self.max_luminance,
+            },
+            &pass_camera,
+            &[],
+            None,
+            None,
+        );
+        textures.adapted.swap(0, 1);
+        textures.has_history = true;
+    }
+}
+
+struct LuminancePass {}
+
+impl Effect for LuminancePass {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}",
+            color_texture.unwrap().fragment_shader_source(),
+            include_str!("shaders/auto_exposure_luminance.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, _depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14 | 0b1u16 << 12 | 0b1u16 << 9 | color_texture.unwrap().id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        color_texture.unwrap().use_uniforms(program);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            write_mask: WriteMask::COLOR,
+            ..Default::default()
+        }
+    }
+}
+
+struct AdaptationPass<'a> {
+    luminance: &'a Texture2D,
+    previous: &'a Texture2D,
+    blend_factor: f32,
+    min_luminance: f32,
+    max_luminance: f32,
+}
+
+impl Effect for AdaptationPass<'_> {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        _color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        include_str!("shaders/auto_exposure_adaptation.frag").to_string()
+    }
+
+    fn id(
+        &self,
+        _color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> u16 {
+        0b1u16 << 14 | 0b1u16 << 12 | 0b1u16 << 8
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _camera: &Camera,
+        _lights: &[&dyn Light],
+        _color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        program.use_texture("luminanceMap", self.luminance);
+        program.use_texture("previousMap", self.previous);
+        program.use_uniform("maxLevel", (LUMINANCE_TEXTURE_SIZE as f32).log2().floor());
+        program.use_uniform("blendFactor", self.blend_factor);
+        program.use_uniform("minLuminance", self.min_luminance.max(0.0001));
+        program.use_uniform(
+            "maxLuminance",
+            self.max_luminance.max(self.min_luminance.max(0.0001)),
+        );
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            write_mask: WriteMask::COLOR,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use crate::renderer::*;
+use std::sync::RwLock;
+
+///
+/// An effect that simulates the glow around bright areas of the image, ie. light bleeding into the surrounding area.
+///
+/// The parts of the scene that are brighter than the [BloomEffect::threshold] are extracted and blurred by successively downsampling them into a chain of textures of decreasing size
+/// and then upsampling and accumulating the result back up the chain. Finally, the blurred result is added to the scene color before the tone and color mapping specified in the [Camera] is applied.
+/// Therefore, the effect should be given the scene color in linear HDR color space, for example rendered using a [PostProcessStack].
+///
+pub struct BloomEffect {
+    /// The brightness, measured as the maximum of the red, green and blue color channels, above which a color contributes to the bloom.
+    pub threshold: f32,
+    /// The width of the soft transition around the [BloomEffect::threshold], a value of zero gives a hard cutoff.
+    pub knee: f32,
+    /// Scales the size of the filter used when upsampling, ie. how far the light spreads out at each level.
+    pub radius: f32,
+    /// The amount of bloom which is added to the scene color.
+    pub intensity: f32,
+    /// The number of textures in the chain, each half the size of the previous. More levels gives a wider bloom.
+    pub levels: u32,
+    mip_chain: RwLock<Vec<Texture2D>>,
+}
+
+impl Default for BloomEffect {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            knee: 0.5,
+            radius: 1.0,
+            intensity: 0.1,
+            levels: 6,
+            mip_chain: RwLock::new(Vec::new()),
+        }
+    }
+}
+
+impl BloomEffect {
+    fn resize(&self, context: &Context, width: u32, height: u32) {
+        let mut sizes = Vec::new();
+        let (mut w, mut h) = (width, height);
+        for _ in 0..self.levels.max(1) {
+            w = (w / 2).max(1);
+            h = (h / 2).max(1);
+            sizes.push((w, h));
+            if w == 1 && h == 1 {
+                break;
+            }
+        }
+        let mut mip_chain = self.mip_chain.write().unwrap();
+        if mip_chain.len() != sizes.len()
+            || mip_chain
+                .iter()
+                .zip(sizes.iter())
+                .any(|(t, (w, h))| t.width() != *w || t.height() != *h)
+        {
+            *mip_chain = sizes
+                .into_iter()
+                .map(|(w, h)| {
+                    Texture2D::new_empty::<[f16; 4]>(
+                        context,
+                        w,
+                        h,
+                        Interpolation::Linear,
+                        Interpolation::Linear,
+                        None,
+                        Wrapping::ClampToEdge,
+                        Wrapping::ClampToEdge,
+                    )
+                })
+                .collect();
+        }
+    }
+}
+
+impl Effect for BloomEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            include_str!("../../core/shared.frag"),
+            color_texture
+                .expect("Must supply a color texture to apply a bloom effect")
+                .fragment_shader_source(),
+            depth_texture
+                .map(|t| format!("#define USE_DEPTH\n{}", t.fragment_shader_source()))
+                .unwrap_or_default(),
+            ToneMapping::fragment_shader_source(),
+            ColorMapping::fragment_shader_source(),
+            include_str!("shaders/bloom_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 9
+            | color_texture
+                .expect("Must supply a color texture to apply a bloom effect")
+                .id()
+            | depth_texture.map(|t| t.id()).unwrap_or(0u16)
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.color_mapping.use_uniforms(program);
+        color_texture
+            .expect("Must supply a color texture to apply a bloom effect")
+            .use_uniforms(program);
+        if let Some(depth_texture) = depth_texture {
+            depth_texture.use_uniforms(program);
+        }
+        program.use_texture(
+            "bloomTexture",
+            self.mip_chain
+                .read()
+                .unwrap()
+                .first()
+                .expect("The bloom effect must be preprocessed before it is applied"),
+        );
+        program.use_uniform("bloomIntensity", self.intensity);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+
+    fn preprocess(
+        &self,
+        context: &Context,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        let color_texture =
+            color_texture.expect("Must supply a color texture to apply a bloom effect");
+        self.resize(context, color_texture.width(), color_texture.height());
+        let mut mip_chain = self.mip_chain.write().unwrap();
+        let mut pass_camera = camera.clone();
+        pass_camera.disable_tone_and_color_mapping();
+
+        // Downsample, extracting the bright parts of the scene in the first pass
+        for i in 0..mip_chain.len() {
+            let (sources, targets) = mip_chain.split_at_mut(i);
+            let target = &mut targets[0];
+            let source = sources
+                .last()
+                .map(ColorTexture::Single)
+                .unwrap_or(color_texture);
+            pass_camera.set_viewport(Viewport::new_at_origo(target.width(), target.height()));
+            target.as_color_target(None).apply_screen_effect(
+                &BloomPass::Downsample {
+                    prefilter: i == 0,
+                    threshold: self.threshold,
+                    knee: self.knee,
+                },
+                &pass_camera,
+                &[],
+                Some(source),
+                None,
+            );
+        }
+
+        // Upsample and accumulate into the next larger texture
+        for i in (1..mip_chain.len()).rev() {
+            let (targets, sources) = mip_chain.split_at_mut(i);
+            let target = &mut targets[i - 1];
+            pass_camera.set_viewport(Viewport::new_at_origo(target.width(), target.height()));
+            target.as_color_target(None).apply_screen_effect(
+                &BloomPass::Upsample {
+                    radius: self.radius,
+                },
+                &pass_camera,
+                &[],
+                Some(ColorTexture::Single(&sources[0])),
+                None,
+            );
+        }
+    }
+}
+
+///
+/// A single pass in the mip chain of the [BloomEffect].
+///
+enum BloomPass {
+    Downsample {
+        prefilter: bool,
+        threshold: f32,
+        knee: f32,
+    },
+    Upsample {
+        radius: f32,
+    },
+}
+
+impl Effect for BloomPass {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        let color_texture =
+            color_texture.expect("Must supply a color texture to apply a bloom pass");
+        match self {
+            Self::Downsample { prefilter, .. } => format!(
+                "{}{}\n{}",
+                if *prefilter {
+                    "#define PREFILTER\n"
+                } else {
+                    ""
+                },
+                color_texture.fragment_shader_source(),
+                include_str!("shaders/bloom_downsample.frag")
+            ),
+            Self::Upsample { .. } => format!(
+                "{}\n{}",
+                color_texture.fragment_shader_source(),
+                include_str!("shaders/bloom_upsample.frag")
+            ),
+        }
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, _depth_texture: Option<DepthTexture>) -> u16 {
+        let color_id = color_texture
+            .expect("Must supply a color texture to apply a bloom pass")
+            .id();
+        match self {
+            Self::Downsample { prefilter, .. } => {
+                0b1u16 << 14
+                    | 0b1u16 << 9
+                    | 0b1u16 << 8
+                    | if *prefilter { 0b1u16 << 2 } else { 0 }
+                    | color_id
+            }
+            Self::Upsample { .. } => 0b1u16 << 14 | 0b1u16 << 9 | 0b1u16 << 7 | color_id,
+        }
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        let color_texture =
+            color_texture.expect("Must supply a color texture to apply a bloom pass");
+        color_texture.use_uniforms(program);
+        program.use_uniform(
+            "texelSize",
+            vec2(
+                1.0 / color_texture.width() as f32,
+                1.0 / color_texture.height() as f32,
+            ),
+        );
+        match self {
+            Self::Downsample {
+                threshold, knee, ..
+            } => {
+                program.use_uniform_if_required("threshold", *threshold);
+                program.use_uniform_if_required("knee", knee.max(0.0));
+            }
+            Self::Upsample { radius } => {
+                program.use_uniform("radius", *radius);
+            }
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            write_mask: WriteMask::COLOR,
+            blend: match self {
+                Self::Downsample { .. } => Blend::Disabled,
+                Self::Upsample { .. } => Blend::ADD,
+            },
+            ..Default::default()
+        }
+    }
+}
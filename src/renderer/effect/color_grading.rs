@@ -0,0 +1,313 @@
+use crate::renderer::*;
+
+///
+/// An effect that adjusts the colors of the image, for example the contrast, saturation or color balance, by looking up each color in a 3D color lookup table (LUT).
+///
+/// The lookup table is applied after the tone mapping specified in the [Camera], to the colors in sRGB color space, which is the convention used by most tools that export lookup tables,
+/// and then the color mapping specified in the [Camera] is applied.
+/// Therefore, the effect must be the final pass that renders into the screen. When using a [PostProcessStack], assign it to [PostProcessStack::color_grading] instead of adding it to the effects.
+///
+pub struct ColorGradingEffect {
+    /// The lookup table, where the red, green and blue input values correspond to the x, y and z texture coordinates respectively.
+    pub lut: Texture3D,
+    /// The input value that maps to the first entry in the lookup table in each dimension.
+    pub domain_min: Vec3,
+    /// The input value that maps to the last entry in the lookup table in each dimension.
+    pub domain_max: Vec3,
+    /// How much of the graded color is used, where zero gives the original color and one gives the color from the lookup table.
+    pub intensity: f32,
+}
+
+const MAX_LUT_SIZE: u32 = 256;
+
+impl ColorGradingEffect {
+    ///
+    /// Constructs a new color grading effect using the given lookup table with the domain `[0,1]`.
+    ///
+    pub fn new(lut: Texture3D) -> Self {
+        Self {
+            lut,
+            domain_min: vec3(0.0, 0.0, 0.0),
+            domain_max: vec3(1.0, 1.0, 1.0),
+            intensity: 1.0,
+        }
+    }
+
+    ///
+    /// Constructs a new color grading effect from the content of a `.cube` file, the lookup table format specified by Adobe and supported by most color grading tools.
+    /// Only 3D lookup tables with a size of at most 256 are supported. Unknown keywords are ignored.
+    ///
+    pub fn from_cube(context: &Context, source: &str) -> Result<Self, RendererError> {
+        let cube = CubeLut::parse(source)?;
+
+        // The red component changes fastest, which is the same order as the texels in a 3D texture
+        let mut lut = Texture3D::new_empty::<[f16; 3]>(
+            context,
+            cube.size,
+            cube.size,
+            cube.size,
+            Interpolation::Linear,
+            Interpolation::Linear,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        lut.fill(&cube.data);
+        Ok(Self {
+            domain_min: cube.domain_min,
+            domain_max: cube.domain_max,
+            ..Self::new(lut)
+        })
+    }
+}
+
+///
+/// The content of a `.cube` file.
+///
+struct CubeLut {
+    size: u32,
+    domain_min: Vec3,
+    domain_max: Vec3,
+    data: Vec<[f16; 3]>,
+}
+
+impl CubeLut {
+    fn parse(source: &str) -> Result<Self, RendererError> {
+        let error = |message: String| RendererError::InvalidColorLookupTable(message);
+        let parse_vector = |values: &[&str], line: &str| -> Result<Vec3, RendererError> {
+            let values = values
+                .iter()
+                .map(|v| v.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| error(format!("{} in the line '{}'", e, line)))?;
+            if values.len() != 3 {
+                Err(error(format!(
+                    "expected three values in the line '{}'",
+                    line
+                )))
+            } else {
+                Ok(vec3(values[0], values[1], values[2]))
+            }
+        };
+
+        let mut size = None;
+        let mut domain_min = vec3(0.0, 0.0, 0.0);
+        let mut domain_max = vec3(1.0, 1.0, 1.0);
+        let mut data = Vec::new();
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            match words[0] {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => return Err(error("1D lookup tables are not supported".into())),
+                "LUT_3D_SIZE" => {
+                    size = Some(
+                        words
+                            .get(1)
+                            .and_then(|s| s.parse::<u32>().ok())
+                            .filter(|s| (2..=MAX_LUT_SIZE).contains(s))
+                            .ok_or_else(|| error(format!("invalid size in the line '{}'", line)))?,
+                    );
+                }
+                "DOMAIN_MIN" => domain_min = parse_vector(&words[1..], line)?,
+                "DOMAIN_MAX" => domain_max = parse_vector(&words[1..], line)?,
+                "LUT_3D_INPUT_RANGE" => {
+                    let range = words[1..]
+                        .iter()
+                        .map(|v| v.parse::<f32>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .ok()
+                        .filter(|r| r.len() == 2)
+                        .ok_or_else(|| {
+                            error(format!("expected two values in the line '{}'", line))
+                        })?;
+                    domain_min = vec3(range[0], range[0], range[0]);
+                    domain_max = vec3(range[1], range[1], range[1]);
+                }
+                keyword if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {}
+                _ => {
+                    let color = parse_vector(&words, line)?;
+                    data.push([
+                        f16::from_f32(color.x),
+                        f16::from_f32(color.y),
+                        f16::from_f32(color.z),
+                    ]);
+                }
+            }
+        }
+        let size = size.ok_or_else(|| error("missing LUT_3D_SIZE".into()))?;
+        if data.len() != (size * size * size) as usize {
+            return Err(error(format!(
+                "expected {} entries for a lookup table of size {}, actual number of entries is {}",
+                size * size * size,
+                size,
+                data.len()
+            )));
+        }
+
+        Ok(Self {
+            size,
+            domain_min,
+            domain_max,
+            data,
+        })
+    }
+}
+
+impl Effect for ColorGradingEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}",
+            color_texture
+                .expect("Must supply a color texture to apply a color grading effect")
+                .fragment_shader_source(),
+            depth_texture
+                .map(|t| format!("#define USE_DEPTH\n{}", t.fragment_shader_source()))
+                .unwrap_or_default(),
+            ToneMapping::fragment_shader_source(),
+            ColorMapping::fragment_shader_source(),
+            include_str!("shaders/color_grading_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 11
+            | 0b1u16 << 10
+            | color_texture
+                .expect("Must supply a color texture to apply a color grading effect")
+                .id()
+            | depth_texture.map(|t| t.id()).unwrap_or(0u16)
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.color_mapping.use_uniforms(program);
+        color_texture
+            .expect("Must supply a color texture to apply a color grading effect")
+            .use_uniforms(program);
+        if let Some(depth_texture) = depth_texture {
+            depth_texture.use_uniforms(program);
+        }
+        program.use_texture_3d("lutMap", &self.lut);
+        program.use_uniform(
+            "lutSize",
+            vec3(
+                self.lut.width() as f32,
+                self.lut.height() as f32,
+                self.lut.depth() as f32,
+            ),
+        );
+        program.use_uniform("domainMin", self.domain_min);
+        program.use_uniform("domainMax", self.domain_max);
+        program.use_uniform("intensity", self.intensity);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINIMAL: &str = "LUT_3D_SIZE 2
+0 0 0
+1 0 0
+0 1 0
+1 1 0
+0 0 1
+1 0 1
+0 1 1
+1 1 1
+";
+
+    fn error_message(source: &str) -> String {
+        match CubeLut::parse(source) {
+            Err(RendererError::InvalidColorLookupTable(message)) => message,
+            Err(e) => panic!("unexpected error {:?}", e),
+            Ok(_) => panic!("expected the lookup table to be invalid"),
+        }
+    }
+
+    #[test]
+    fn parse_minimal_cube() {
+        let cube = CubeLut::parse(MINIMAL).unwrap();
+        assert_eq!(cube.size, 2);
+        assert_eq!(cube.domain_min, vec3(0.0, 0.0, 0.0));
+        assert_eq!(cube.domain_max, vec3(1.0, 1.0, 1.0));
+        assert_eq!(cube.data.len(), 8);
+        let one = f16::from_f32(1.0);
+        let zero = f16::from_f32(0.0);
+        assert_eq!(cube.data[1], [one, zero, zero]);
+        assert_eq!(cube.data[6], [zero, one, one]);
+    }
+
+    #[test]
+    fn parse_cube_with_wrong_entry_count() {
+        let source = MINIMAL.replace("1 1 1\n", "");
+        assert!(error_message(&source).contains("actual number of entries is 7"));
+        let source = format!("{}0.5 0.5 0.5\n", MINIMAL);
+        assert!(error_message(&source).contains("actual number of entries is 9"));
+    }
+
+    #[test]
+    fn parse_cube_with_invalid_size() {
+        assert!(error_message("LUT_3D_SIZE 257\n").contains("invalid size"));
+        assert!(error_message("LUT_3D_SIZE 1\n0 0 0\n").contains("invalid size"));
+        assert!(error_message("LUT_3D_SIZE\n").contains("invalid size"));
+        assert!(error_message("0 0 0\n").contains("missing LUT_3D_SIZE"));
+        assert!(error_message("LUT_1D_SIZE 2\n").contains("not supported"));
+    }
+
+    #[test]
+    fn parse_cube_with_input_range() {
+        let cube = CubeLut::parse(&format!("LUT_3D_INPUT_RANGE -0.5 2.0\n{}", MINIMAL)).unwrap();
+        assert_eq!(cube.domain_min, vec3(-0.5, -0.5, -0.5));
+        assert_eq!(cube.domain_max, vec3(2.0, 2.0, 2.0));
+        assert!(
+            error_message(&format!("LUT_3D_INPUT_RANGE 0.0\n{}", MINIMAL))
+                .contains("expected two values")
+        );
+    }
+
+    #[test]
+    fn parse_cube_with_comments_and_unknown_keywords() {
+        let source = format!(
+            "# Created by a tool\nTITLE \"Warm\"\n\n  # indented comment\nLUT_3D_SIZE_UNKNOWN 4\nDOMAIN_MIN 0 0 0\nDOMAIN_MAX 1 1 1\nCUSTOM_KEYWORD a b c\n{}",
+            MINIMAL
+        );
+        let cube = CubeLut::parse(&source).unwrap();
+        assert_eq!(cube.size, 2);
+        assert_eq!(cube.data.len(), 8);
+        assert!(error_message(&format!("{}1 x 1\n", MINIMAL)).contains("in the line '1 x 1'"));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::renderer::*;
+
+///
+/// An effect that simulates the depth of field of a physical camera, ie. that only objects at a certain distance from the camera are in focus and objects closer or further away are blurred.
+///
+/// The size of the blur, the circle of confusion, is calculated for each pixel from the depth texture using a thin lens model with the given focus distance and f-stop
+/// and the focal length derived from the field of view of the [Camera] and the sensor height.
+/// The blur is gathered in the shape of the aperture, which gives the characteristic bokeh, and the near and far field are gathered separately
+/// so that blurred objects in front of the focus distance bleed over objects in focus while blurred objects behind do not.
+/// The depth of field is only applied when using a camera with a perspective projection.
+///
+#[derive(Clone, Debug)]
+pub struct DepthOfFieldEffect {
+    /// The distance from the camera to the plane in focus.
+    pub focus_distance: f32,
+    /// The f-stop, ie. the focal length divided by the diameter of the aperture. A lower value gives a more shallow depth of field.
+    pub f_stop: f32,
+    /// The height of the camera sensor in the same unit as the scene, which together with the field of view of the camera determines the focal length.
+    /// The default is 0.024, which corresponds to a 35mm full frame sensor when the scene is in meters.
+    pub sensor_height: f32,
+    /// The number of blades of the aperture which determines the shape of the bokeh. A value less than three gives a circular aperture.
+    pub aperture_blades: u32,
+    /// The maximum radius of the blur in pixels.
+    pub max_blur_radius: f32,
+}
+
+impl Default for DepthOfFieldEffect {
+    fn default() -> Self {
+        Self {
+            focus_distance: 10.0,
+            f_stop: 2.8,
+            sensor_height: 0.024,
+            aperture_blades: 0,
+            max_blur_radius: 16.0,
+        }
+    }
+}
+
+impl DepthOfFieldEffect {
+    ///
+    /// Returns the focal length of the lens given the field of view of the camera and the [DepthOfFieldEffect::sensor_height] or `None` if the camera does not use a perspective projection.
+    ///
+    pub fn focal_length(&self, camera: &Camera) -> Option<f32> {
+        if let three_d_asset::ProjectionType::Perspective { field_of_view_y } =
+            camera.projection_type()
+        {
+            Some(0.5 * self.sensor_height / (0.5 * field_of_view_y.0).tan())
+        } else {
+            None
+        }
+    }
+}
+
+impl Effect for DepthOfFieldEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            include_str!("../../core/shared.frag"),
+            color_texture
+                .expect("Must supply a color texture to apply a depth of field effect")
+                .fragment_shader_source(),
+            depth_texture
+                .expect("Must supply a depth texture to apply a depth of field effect")
+                .fragment_shader_source(),
+            ToneMapping::fragment_shader_source(),
+            ColorMapping::fragment_shader_source(),
+            include_str!("shaders/depth_of_field_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 13
+            | 0b1u16 << 9
+            | color_texture
+                .expect("Must supply a color texture to apply a depth of field effect")
+                .id()
+            | depth_texture
+                .expect("Must supply a depth texture to apply a depth of field effect")
+                .id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.color_mapping.use_uniforms(program);
+        let color_texture =
+            color_texture.expect("Must supply a color texture to apply a depth of field effect");
+        color_texture.use_uniforms(program);
+        depth_texture
+            .expect("Must supply a depth texture to apply a depth of field effect")
+            .use_uniforms(program);
+        let focal_length = self.focal_length(camera);
+        let focus_distance = self
+            .focus_distance
+            .max(focal_length.unwrap_or(0.0) + 0.0001);
+        program.use_uniform(
+            "resolution",
+            vec2(color_texture.width() as f32, color_texture.height() as f32),
+        );
+        program.use_uniform(
+            "viewProjectionInverse",
+            (camera.depth_reconstruction_projection() * camera.view())
+                .invert()
+                .unwrap(),
+        );
+        program.use_uniform("view", camera.view());
+        program.use_uniform("farDepth", camera.depth_mapping.far_depth());
+        program.use_uniform("focusDistance", focus_distance);
+        // The radius of the circle of confusion in pixels is cocScale * (distance - focusDistance) / distance, ie. half the diameter given by the thin lens equation
+        program.use_uniform(
+            "cocScale",
+            focal_length
+                .map(|f| {
+                    let aperture = f / self.f_stop.max(0.0001);
+                    0.5 * aperture * f / (focus_distance - f) / self.sensor_height
+                        * color_texture.height() as f32
+                })
+                .unwrap_or(0.0),
+        );
+        program.use_uniform("maxBlurRadius", self.max_blur_radius.max(0.0));
+        program.use_uniform("apertureBlades", self.aperture_blades as i32);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::renderer::*;
+
+///
+/// An effect that draws lines along the feature edges of the objects in the scene, for example to get a technical illustration look.
+///
+/// The edges are found where the depth changes abruptly, ie. at silhouettes, and, if a normal texture is specified, where the surface normal changes abruptly, ie. at creases.
+/// The lines are drawn on top of the color texture given to the effect or, if no color texture is given, on top of a white background.
+/// The effect is applied to the final colors, ie. after tone and color mapping.
+///
+pub struct EdgeDetectionEffect<'a> {
+    /// The texture containing the surface normals of the scene, either rendered using the [NormalMaterial] or, if it is a texture array, the color texture from the geometry pass of the [DeferredPhysicalMaterial],
+    /// ie. the same as given to [DeferredPhysicalMaterial::lighting_pass]. If not specified, only edges from the depth are detected.
+    pub normal_texture: Option<ColorTexture<'a>>,
+    /// The color of the lines. The alpha value specifies the opacity of the lines.
+    pub color: Srgba,
+    /// The thickness of the lines in pixels.
+    pub thickness: f32,
+    /// The relative change in depth needed for an edge, a lower value detects more edges.
+    /// The change is measured relative to a plane through the neighbouring surface points, so flat surfaces seen at a grazing angle do not give edges.
+    pub depth_threshold: f32,
+    /// The angle between the normals of neighbouring surface points needed for an edge.
+    pub normal_threshold: Radians,
+}
+
+impl<'a> EdgeDetectionEffect<'a> {
+    ///
+    /// Constructs a new edge detection effect which detects edges from the depth and, if given, the normals in the normal texture.
+    ///
+    pub fn new(normal_texture: Option<ColorTexture<'a>>) -> Self {
+        Self {
+            normal_texture,
+            color: Srgba::BLACK,
+            thickness: 1.0,
+            depth_threshold: 0.02,
+            normal_threshold: degrees(30.0).into(),
+        }
+    }
+}
+
+impl Effect for EdgeDetectionEffect<'_> {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            include_str!("../../core/shared.frag"),
+            include_str!("../material/shaders/gbuffer.frag"),
+            match self.normal_texture {
+                Some(ColorTexture::Single(_)) => "#define USE_NORMAL_TEXTURE\n",
+                Some(ColorTexture::Array { .. }) => "#define USE_NORMAL_TEXTURE_ARRAY\n",
+                _ => "",
+            },
+            color_texture
+                .map(|t| format!("#define USE_COLOR\n{}", t.fragment_shader_source()))
+                .unwrap_or_default(),
+            depth_texture
+                .expect("Must supply a depth texture to apply an edge detection effect")
+                .fragment_shader_source(),
+            include_str!("shaders/edge_detection_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 11
+            | 0b1u16 << 8
+            | match self.normal_texture {
+                Some(ColorTexture::Single(_)) => 0b1u16 << 2,
+                Some(ColorTexture::Array { .. }) => 0b1u16 << 5,
+                _ => 0,
+            }
+            | color_texture.map(|t| t.id()).unwrap_or(0u16)
+            | depth_texture
+                .expect("Must supply a depth texture to apply an edge detection effect")
+                .id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        let depth_texture =
+            depth_texture.expect("Must supply a depth texture to apply an edge detection effect");
+        if let Some(color_texture) = color_texture {
+            color_texture.use_uniforms(program);
+        }
+        depth_texture.use_uniforms(program);
+        match self.normal_texture {
+            Some(ColorTexture::Single(texture)) => {
+                program.use_texture("normalMap", texture);
+            }
+            Some(ColorTexture::Array { texture, layers }) => {
+                program.use_texture_array("normalMap", texture);
+                program.use_uniform(
+                    "normalLayer",
+                    *layers
+                        .get(1)
+                        .expect("The geometry pass color texture must contain the normal layer")
+                        as i32,
+                );
+            }
+            Some(ColorTexture::CubeMap { .. }) => {
+                panic!("The normal texture must be a 2D texture or a texture array")
+            }
+            None => {}
+        }
+        program.use_uniform(
+            "texelSize",
+            vec2(
+                1.0 / depth_texture.width() as f32,
+                1.0 / depth_texture.height() as f32,
+            ),
+        );
+        program.use_uniform(
+            "projectionInverse",
+            camera.depth_reconstruction_projection().invert().unwrap(),
+        );
+        program.use_uniform("farDepth", camera.depth_mapping.far_depth());
+        program.use_uniform("lineColor", Vec4::from(self.color));
+        program.use_uniform("thickness", self.thickness.max(0.0));
+        program.use_uniform("depthThreshold", self.depth_threshold.max(0.0));
+        program.use_uniform_if_required("normalThreshold", self.normal_threshold.0.cos());
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
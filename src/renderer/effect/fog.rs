@@ -2,6 +2,7 @@ use crate::renderer::*;
 
 ///
 /// An effect that simulates fog, ie. the area where it is applied gets hazy when objects are far away.
+/// See [FogSettings] for a fog with a physically motivated density, for example height fog.
 ///
 #[derive(Clone, Debug)]
 pub struct FogEffect {
@@ -85,13 +86,187 @@ impl Effect for FogEffect {
             .use_uniforms(program);
         program.use_uniform(
             "viewProjectionInverse",
-            (camera.projection() * camera.view()).invert().unwrap(),
+            (camera.depth_reconstruction_projection() * camera.view())
+                .invert()
+                .unwrap(),
         );
         program.use_uniform("fogColor", Vec4::from(self.color));
         program.use_uniform("fogDensity", self.density);
         program.use_uniform("animation", self.animation);
         program.use_uniform("time", 0.001 * self.time);
         program.use_uniform("eyePosition", camera.position());
+        program.use_uniform("farDepth", camera.depth_mapping.far_depth());
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
+
+///
+/// Defines how the density of the fog described by [FogSettings] is distributed.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FogMode {
+    /// The fog has a constant density, so the amount of fog increases exponentially with the distance to the camera.
+    Exponential,
+    /// The amount of fog increases exponentially with the squared distance to the camera, which gives a clearer area close to the camera and a steeper transition further away.
+    ExponentialSquared,
+    /// The density of the fog decreases exponentially with the height, for example to simulate fog gathering in valleys.
+    Height {
+        /// The height at which the fog has the density specified in [FogSettings::density].
+        base_height: f32,
+        /// How fast the density decreases with the height above the base height. A higher value gives a thinner layer of fog.
+        falloff: f32,
+    },
+}
+
+///
+/// Settings for a physically motivated fog, which is applied as an effect to a rendered scene using the depth texture.
+///
+/// The amount of fog is calculated by integrating the density given by the [FogSettings::mode] along the view ray from the camera to each surface.
+/// The color of the fog can be taken from an [Environment] and brightened in the direction of a [DirectionalLight] to simulate the sun light scattered in the fog.
+/// The fog is applied to the scene color before the tone and color mapping specified in the [Camera] is applied.
+///
+#[derive(Clone, Copy)]
+pub struct FogSettings<'a> {
+    /// The distribution of the fog density.
+    pub mode: FogMode,
+    /// The color of the fog. If an environment is specified, this color is multiplied with the color of the environment.
+    pub color: Srgba,
+    /// The density of the fog, ie. how much of the light is absorbed or scattered per unit of distance.
+    pub density: f32,
+    /// The directional light, usually the sun, which is scattered towards the camera when looking towards the light.
+    pub sun: Option<&'a DirectionalLight>,
+    /// An exponent which determines the size of the area around the sun where the light is scattered, a higher value gives a smaller area.
+    pub sun_exponent: f32,
+    /// The environment which gives the color of the fog in each direction.
+    pub environment: Option<&'a Environment>,
+}
+
+impl Default for FogSettings<'_> {
+    fn default() -> Self {
+        Self {
+            mode: FogMode::Exponential,
+            color: Srgba::new_opaque(200, 210, 220),
+            density: 0.02,
+            sun: None,
+            sun_exponent: 8.0,
+            environment: None,
+        }
+    }
+}
+
+impl Effect for FogSettings<'_> {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}{}\n{}\n{}\n{}\n{}\n{}",
+            include_str!("../../core/shared.frag"),
+            if self.sun.is_some() {
+                "#define USE_SUN\n"
+            } else {
+                ""
+            },
+            if self.environment.is_some() {
+                "#define USE_ENVIRONMENT\n"
+            } else {
+                ""
+            },
+            color_texture
+                .expect("Must supply a color texture to apply a fog effect")
+                .fragment_shader_source(),
+            depth_texture
+                .expect("Must supply a depth texture to apply a fog effect")
+                .fragment_shader_source(),
+            ToneMapping::fragment_shader_source(),
+            ColorMapping::fragment_shader_source(),
+            include_str!("shaders/fog_settings_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 10
+            | 0b1u16 << 9
+            | if self.sun.is_some() { 0b1u16 << 2 } else { 0 }
+            | if self.environment.is_some() {
+                0b1u16 << 5
+            } else {
+                0
+            }
+            | color_texture
+                .expect("Must supply a color texture to apply a fog effect")
+                .id()
+            | depth_texture
+                .expect("Must supply a depth texture to apply a fog effect")
+                .id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.color_mapping.use_uniforms(program);
+        color_texture
+            .expect("Must supply a color texture to apply a fog effect")
+            .use_uniforms(program);
+        depth_texture
+            .expect("Must supply a depth texture to apply a fog effect")
+            .use_uniforms(program);
+        program.use_uniform(
+            "viewProjectionInverse",
+            (camera.depth_reconstruction_projection() * camera.view())
+                .invert()
+                .unwrap(),
+        );
+        program.use_uniform("eyePosition", camera.position());
+        program.use_uniform("farDepth", camera.depth_mapping.far_depth());
+        program.use_uniform("skyDistance", camera.z_far());
+        program.use_uniform("fogColor", self.color.to_linear_srgb().truncate());
+        program.use_uniform("fogDensity", self.density.max(0.0));
+        let (mode, base_height, falloff) = match self.mode {
+            FogMode::Exponential => (0, 0.0, 0.0),
+            FogMode::ExponentialSquared => (1, 0.0, 0.0),
+            FogMode::Height {
+                base_height,
+                falloff,
+            } => (2, base_height, falloff.max(0.0)),
+        };
+        program.use_uniform("fogMode", mode);
+        program.use_uniform_if_required("baseHeight", base_height);
+        program.use_uniform_if_required("heightFalloff", falloff);
+        if let Some(sun) = self.sun {
+            program.use_uniform("sunDirection", sun.direction.normalize());
+            program.use_uniform(
+                "sunColor",
+                sun.color.to_linear_srgb().truncate() * sun.intensity,
+            );
+            program.use_uniform("sunExponent", self.sun_exponent.max(0.0));
+        }
+        if let Some(environment) = self.environment {
+            program.use_texture_cube("irradianceMap", &environment.irradiance_map);
+        }
     }
 
     fn render_states(&self) -> RenderStates {
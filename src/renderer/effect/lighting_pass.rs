@@ -20,6 +20,7 @@ impl Effect for LightingPassEffect {
         fragment_shader.push_str(&depth_texture.unwrap().fragment_shader_source());
         fragment_shader.push_str(ToneMapping::fragment_shader_source());
         fragment_shader.push_str(ColorMapping::fragment_shader_source());
+        fragment_shader.push_str(include_str!("../material/shaders/gbuffer.frag"));
         fragment_shader.push_str(include_str!("shaders/deferred_lighting.frag"));
         fragment_shader
     }
@@ -48,12 +49,15 @@ impl Effect for LightingPassEffect {
         color_texture.unwrap().use_uniforms(program);
         depth_texture.unwrap().use_uniforms(program);
         program.use_uniform_if_required("cameraPosition", camera.position());
+        program.use_uniform("farDepth", camera.depth_mapping.far_depth());
         for (i, light) in lights.iter().enumerate() {
             light.use_uniforms(program, i as u32);
         }
         program.use_uniform_if_required(
             "viewProjectionInverse",
-            (camera.projection() * camera.view()).invert().unwrap(),
+            (camera.depth_reconstruction_projection() * camera.view())
+                .invert()
+                .unwrap(),
         );
         program.use_uniform("debug_type", DebugType::None as i32);
     }
@@ -0,0 +1,122 @@
+use crate::renderer::*;
+
+///
+/// An effect that simulates the blur of objects moving while the shutter of a camera is open.
+///
+/// The effect needs the velocity of each pixel rendered using the [VelocityMaterial]. Either give the effect a color texture array where the first layer contains the scene color
+/// and the second layer contains the velocity, for example `ColorTexture::Array { texture: &texture, layers: &[0, 1] }`,
+/// or call [MotionBlurEffect::render_velocity] each frame and give the effect a single color texture, which is also how the effect is used in a [PostProcessStack].
+/// The blur is applied before the tone and color mapping specified in the [Camera].
+///
+pub struct MotionBlurEffect {
+    /// The fraction of the time between two frames that the shutter is open, ie. a scale applied to the velocity.
+    pub intensity: f32,
+    /// The number of samples along the velocity of each pixel. Higher gives a smoother blur at the cost of performance.
+    pub samples: u32,
+    /// The maximum length of the blur in pixels.
+    pub max_length: f32,
+    velocity: super::VelocityTexture,
+}
+
+impl MotionBlurEffect {
+    ///
+    /// Renders the velocity of the given geometries as seen from the given camera into a texture owned by the effect,
+    /// which is used when the effect is applied to a single color texture.
+    /// Call this each frame before applying the effect, using the camera used for rendering the scene.
+    ///
+    pub fn render_velocity(
+        &self,
+        context: &Context,
+        camera: &Camera,
+        geometries: impl IntoIterator<Item = impl Geometry>,
+    ) {
+        self.velocity.render(context, camera, geometries)
+    }
+}
+
+impl Default for MotionBlurEffect {
+    fn default() -> Self {
+        Self {
+            intensity: 0.5,
+            samples: 16,
+            max_length: 32.0,
+            velocity: Default::default(),
+        }
+    }
+}
+
+impl Effect for MotionBlurEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            color_texture
+                .expect("Must supply a color texture to apply a motion blur effect")
+                .fragment_shader_source(),
+            super::VelocityTexture::fragment_shader_source(color_texture, "motion blur"),
+            depth_texture
+                .map(|t| format!("#define USE_DEPTH\n{}", t.fragment_shader_source()))
+                .unwrap_or_default(),
+            ToneMapping::fragment_shader_source(),
+            ColorMapping::fragment_shader_source(),
+            include_str!("shaders/motion_blur_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 12
+            | 0b1u16 << 10
+            | color_texture
+                .expect("Must supply a color texture to apply a motion blur effect")
+                .id()
+            | super::VelocityTexture::id(color_texture, "motion blur")
+            | depth_texture.map(|t| t.id()).unwrap_or(0u16)
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.color_mapping.use_uniforms(program);
+        self.velocity
+            .use_uniforms(program, color_texture, "motion blur");
+        let color_texture =
+            color_texture.expect("Must supply a color texture to apply a motion blur effect");
+        color_texture.use_uniforms(program);
+        if let Some(depth_texture) = depth_texture {
+            depth_texture.use_uniforms(program);
+        }
+        program.use_uniform(
+            "resolution",
+            vec2(color_texture.width() as f32, color_texture.height() as f32),
+        );
+        program.use_uniform("intensity", self.intensity);
+        program.use_uniform("sampleCount", self.samples.max(1) as i32);
+        program.use_uniform("maxLength", self.max_length);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
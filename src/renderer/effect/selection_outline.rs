@@ -0,0 +1,353 @@
+use crate::renderer::*;
+use std::sync::RwLock;
+
+///
+/// An effect that draws an outline around selected objects, for example to highlight the selection in an editor.
+///
+/// Call [SelectionOutlineEffect::render_selection] each frame with the selected geometries, which renders them into a mask and depth texture owned by the effect,
+/// and then apply the effect to the rendered scene. The mask is dilated by the [SelectionOutlineEffect::width] to find the outline
+/// using jump flooding, which needs a number of passes that grows with the logarithm of the width,
+/// which is drawn with the [SelectionOutlineEffect::visible_opacity] where the selected objects are visible and with the [SelectionOutlineEffect::occluded_opacity]
+/// where they are occluded by other objects in the scene.
+/// The effect must be applied using the same camera viewport as the one used to render the selection and is applied to the final colors, ie. after tone and color mapping.
+///
+pub struct SelectionOutlineEffect {
+    context: Context,
+    /// The color of the outline. The alpha value is multiplied with the opacity.
+    pub color: Srgba,
+    /// The width of the outline in pixels.
+    pub width: f32,
+    /// The opacity of the outline where the selected objects are visible.
+    pub visible_opacity: f32,
+    /// The opacity of the outline where the selected objects are occluded by other objects in the scene.
+    pub occluded_opacity: f32,
+    mask_texture: Texture2D,
+    mask_depth_texture: DepthTexture2D,
+    seed_textures: RwLock<Vec<Texture2D>>,
+}
+
+const MAX_WIDTH: f32 = 32.0;
+
+impl SelectionOutlineEffect {
+    ///
+    /// Creates a new selection outline effect with default settings.
+    /// Nothing is outlined until [SelectionOutlineEffect::render_selection] is called.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            color: Srgba::new_opaque(255, 165, 0),
+            width: 3.0,
+            visible_opacity: 1.0,
+            occluded_opacity: 0.3,
+            mask_texture: Self::new_mask_texture(context, 1, 1),
+            mask_depth_texture: Self::new_mask_depth_texture(context, 1, 1),
+            seed_textures: RwLock::new(Vec::new()),
+        }
+    }
+
+    ///
+    /// Renders the selected geometries as seen from the given camera into the mask and depth texture used to find the outline.
+    /// Use an empty iterator to clear the selection.
+    ///
+    pub fn render_selection(
+        &mut self,
+        camera: &Camera,
+        geometries: impl IntoIterator<Item = impl Geometry>,
+    ) {
+        let viewport = camera.viewport();
+        if self.mask_texture.width() != viewport.width
+            || self.mask_texture.height() != viewport.height
+        {
+            self.mask_texture =
+                Self::new_mask_texture(&self.context, viewport.width, viewport.height);
+            self.mask_depth_texture =
+                Self::new_mask_depth_texture(&self.context, viewport.width, viewport.height);
+        }
+        let mut mask_camera = camera.clone();
+        mask_camera.disable_tone_and_color_mapping();
+        mask_camera.set_viewport(Viewport::new_at_origo(viewport.width, viewport.height));
+        let mask_material = ColorMaterial {
+            color: Srgba::WHITE,
+            ..Default::default()
+        };
+        RenderTarget::new(
+            self.mask_texture.as_color_target(None),
+            self.mask_depth_texture.as_depth_target(),
+        )
+        .clear(ClearState::color_and_depth(
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            camera.depth_mapping.far_depth(),
+        ))
+        .write::<RendererError>(|| {
+            for geometry in geometries
+                .into_iter()
+                .filter(|g| mask_camera.in_frustum(&g.aabb()))
+            {
+                render_with_material(&self.context, &mask_camera, &geometry, &mask_material, &[]);
+            }
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    fn new_mask_texture(context: &Context, width: u32, height: u32) -> Texture2D {
+        let mut texture = Texture2D::new_empty::<u8>(
+            context,
+            width,
+            height,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        texture
+            .as_color_target(None)
+            .clear(ClearState::color(0.0, 0.0, 0.0, 0.0));
+        texture
+    }
+
+    fn new_mask_depth_texture(context: &Context, width: u32, height: u32) -> DepthTexture2D {
+        DepthTexture2D::new::<f32>(
+            context,
+            width,
+            height,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        )
+    }
+}
+
+impl Effect for SelectionOutlineEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}",
+            include_str!("../../core/shared.frag"),
+            color_texture
+                .expect("Must supply a color texture to apply a selection outline effect")
+                .fragment_shader_source(),
+            depth_texture
+                .expect("Must supply a depth texture to apply a selection outline effect")
+                .fragment_shader_source(),
+            include_str!("shaders/selection_outline_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 11
+            | 0b1u16 << 9
+            | color_texture
+                .expect("Must supply a color texture to apply a selection outline effect")
+                .id()
+            | depth_texture
+                .expect("Must supply a depth texture to apply a selection outline effect")
+                .id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        color_texture
+            .expect("Must supply a color texture to apply a selection outline effect")
+            .use_uniforms(program);
+        depth_texture
+            .expect("Must supply a depth texture to apply a selection outline effect")
+            .use_uniforms(program);
+        program.use_texture(
+            "seedMap",
+            self.seed_textures
+                .read()
+                .unwrap()
+                .first()
+                .expect("The selection outline effect must be preprocessed before it is applied"),
+        );
+        program.use_depth_texture("maskDepthMap", &self.mask_depth_texture);
+        program.use_uniform(
+            "texelSize",
+            vec2(
+                1.0 / self.mask_texture.width() as f32,
+                1.0 / self.mask_texture.height() as f32,
+            ),
+        );
+        program.use_uniform(
+            "reversedDepth",
+            if camera.depth_mapping.far_depth() < 0.5 {
+                1
+            } else {
+                0
+            },
+        );
+        program.use_uniform("outlineColor", Vec4::from(self.color));
+        program.use_uniform("outlineWidth", self.width.clamp(0.0, MAX_WIDTH));
+        program.use_uniform("visibleOpacity", self.visible_opacity);
+        program.use_uniform("occludedOpacity", self.occluded_opacity);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+
+    fn preprocess(
+        &self,
+        _context: &Context,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        _color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        let (width, height) = (self.mask_texture.width(), self.mask_texture.height());
+        let mut seed_textures = self.seed_textures.write().unwrap();
+        if seed_textures
+            .first()
+            .map(|t| t.width() != width || t.height() != height)
+            .unwrap_or(true)
+        {
+            *seed_textures = (0..2)
+                .map(|_| {
+                    Texture2D::new_empty::<[f16; 2]>(
+                        &self.context,
+                        width,
+                        height,
+                        Interpolation::Nearest,
+                        Interpolation::Nearest,
+                        None,
+                        Wrapping::ClampToEdge,
+                        Wrapping::ClampToEdge,
+                    )
+                })
+                .collect();
+        }
+        let mut pass_camera = camera.clone();
+        pass_camera.disable_tone_and_color_mapping();
+        pass_camera.set_viewport(Viewport::new_at_origo(width, height));
+
+        // The first texture always contains the result of the latest pass
+        let max_step = (self.width.clamp(1.0, MAX_WIDTH).ceil() as u32).next_power_of_two();
+        let steps = std::iter::successors(Some(max_step), |step| Some(step / 2))
+            .take_while(|step| *step > 0)
+            .map(Some);
+        for step in std::iter::once(None).chain(steps) {
+            let (source, target) = seed_textures.split_at_mut(1);
+            target[0].as_color_target(None).apply_screen_effect(
+                &JumpFloodPass {
+                    mask: &self.mask_texture,
+                    seeds: &source[0],
+                    step,
+                },
+                &pass_camera,
+                &[],
+                None,
+                None,
+            );
+            seed_textures.swap(0, 1);
+        }
+    }
+}
+
+///
+/// A pass which finds the offset to the closest selected pixel for each pixel in the [SelectionOutlineEffect].
+/// The first pass, without a step, initializes the offsets from the mask and each of the following passes looks for closer selected pixels a step away.
+///
+struct JumpFloodPass<'a> {
+    mask: &'a Texture2D,
+    seeds: &'a Texture2D,
+    step: Option<u32>,
+}
+
+impl Effect for JumpFloodPass<'_> {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        _color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}{}",
+            if self.step.is_none() {
+                "#define INITIALIZE\n"
+            } else {
+                ""
+            },
+            include_str!("shaders/selection_outline_jump_flood.frag")
+        )
+    }
+
+    fn id(
+        &self,
+        _color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 11
+            | 0b1u16 << 9
+            | if self.step.is_none() {
+                0b1u16 << 2
+            } else {
+                0b1u16 << 1
+            }
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _camera: &Camera,
+        _lights: &[&dyn Light],
+        _color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        if let Some(step) = self.step {
+            program.use_texture("seedMap", self.seeds);
+            program.use_uniform(
+                "texelSize",
+                vec2(
+                    1.0 / self.seeds.width() as f32,
+                    1.0 / self.seeds.height() as f32,
+                ),
+            );
+            program.use_uniform("stepSize", step as f32);
+        } else {
+            program.use_texture("maskMap", self.mask);
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            write_mask: WriteMask::COLOR,
+            ..Default::default()
+        }
+    }
+}
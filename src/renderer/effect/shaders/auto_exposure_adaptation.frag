@@ -0,0 +1,19 @@
+
+uniform sampler2D luminanceMap;
+uniform sampler2D previousMap;
+uniform float maxLevel;
+uniform float blendFactor;
+uniform float minLuminance;
+uniform float maxLuminance;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    float averageLuminance = exp2(textureLod(luminanceMap, vec2(0.5), maxLevel).r);
+    averageLuminance = clamp(averageLuminance, minLuminance, maxLuminance);
+    float previousLuminance = texelFetch(previousMap, ivec2(0, 0), 0).r;
+    outColor = vec4(mix(previousLuminance, averageLuminance, blendFactor), 0.0, 0.0, 1.0);
+}
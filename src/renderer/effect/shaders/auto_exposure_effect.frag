@@ -0,0 +1,22 @@
+
+uniform sampler2D adaptedLuminanceMap;
+uniform float compensation;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+// The exposure maps the adapted average luminance to middle gray
+const float KEY_VALUE = 0.18;
+
+void main()
+{
+    float adaptedLuminance = texelFetch(adaptedLuminanceMap, ivec2(0, 0), 0).r;
+    float exposure = KEY_VALUE / max(adaptedLuminance, 0.0001) * exp2(compensation);
+    outColor = sample_color(uvs);
+    outColor.rgb = tone_mapping(exposure * outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+#ifdef USE_DEPTH
+    gl_FragDepth = sample_depth(uvs);
+#endif
+}
@@ -0,0 +1,11 @@
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec3 color = sample_color(uvs).rgb;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    outColor = vec4(log2(max(luminance, 0.0001)), 0.0, 0.0, 1.0);
+}
@@ -0,0 +1,56 @@
+
+uniform vec2 texelSize;
+#ifdef PREFILTER
+uniform float threshold;
+uniform float knee;
+#endif
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+#ifdef PREFILTER
+// Soft threshold with a quadratic transition of width knee around the threshold
+vec3 prefilter(vec3 color)
+{
+    float brightness = max(color.r, max(color.g, color.b));
+    float soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 0.00001);
+    float weight = max(soft, brightness - threshold) / max(brightness, 0.00001);
+    return color * weight;
+}
+#endif
+
+vec3 sample_source(vec2 offset)
+{
+    return max(sample_color(uvs + offset * texelSize).rgb, vec3(0.0));
+}
+
+void main()
+{
+    // 13 tap filter consisting of five overlapping 4x4 box filters
+    vec3 a = sample_source(vec2(-2.0, 2.0));
+    vec3 b = sample_source(vec2(0.0, 2.0));
+    vec3 c = sample_source(vec2(2.0, 2.0));
+    vec3 d = sample_source(vec2(-2.0, 0.0));
+    vec3 e = sample_source(vec2(0.0, 0.0));
+    vec3 f = sample_source(vec2(2.0, 0.0));
+    vec3 g = sample_source(vec2(-2.0, -2.0));
+    vec3 h = sample_source(vec2(0.0, -2.0));
+    vec3 i = sample_source(vec2(2.0, -2.0));
+    vec3 j = sample_source(vec2(-1.0, 1.0));
+    vec3 k = sample_source(vec2(1.0, 1.0));
+    vec3 l = sample_source(vec2(-1.0, -1.0));
+    vec3 m = sample_source(vec2(1.0, -1.0));
+
+    vec3 color = 0.5 * (j + k + l + m) * 0.25;
+    color += 0.125 * (a + b + d + e) * 0.25;
+    color += 0.125 * (b + c + e + f) * 0.25;
+    color += 0.125 * (d + e + g + h) * 0.25;
+    color += 0.125 * (e + f + h + i) * 0.25;
+
+#ifdef PREFILTER
+    color = prefilter(color);
+#endif
+    outColor = vec4(color, 1.0);
+}
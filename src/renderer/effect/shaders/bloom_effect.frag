@@ -0,0 +1,19 @@
+
+uniform sampler2D bloomTexture;
+uniform float bloomIntensity;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec4 color = sample_color(uvs);
+    outColor.rgb = color.rgb + bloomIntensity * texture(bloomTexture, uvs).rgb;
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    outColor.a = color.a;
+#ifdef USE_DEPTH
+    gl_FragDepth = sample_depth(uvs);
+#endif
+}
@@ -0,0 +1,23 @@
+
+uniform vec2 texelSize;
+uniform float radius;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    // 3x3 tent filter
+    vec2 offset = radius * texelSize;
+    vec3 color = 4.0 * sample_color(uvs).rgb;
+    color += 2.0 * sample_color(uvs + vec2(-offset.x, 0.0)).rgb;
+    color += 2.0 * sample_color(uvs + vec2(offset.x, 0.0)).rgb;
+    color += 2.0 * sample_color(uvs + vec2(0.0, -offset.y)).rgb;
+    color += 2.0 * sample_color(uvs + vec2(0.0, offset.y)).rgb;
+    color += sample_color(uvs + vec2(-offset.x, -offset.y)).rgb;
+    color += sample_color(uvs + vec2(offset.x, -offset.y)).rgb;
+    color += sample_color(uvs + vec2(-offset.x, offset.y)).rgb;
+    color += sample_color(uvs + vec2(offset.x, offset.y)).rgb;
+    outColor = vec4(color / 16.0, 1.0);
+}
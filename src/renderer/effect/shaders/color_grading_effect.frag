@@ -0,0 +1,34 @@
+
+uniform sampler3D lutMap;
+uniform vec3 lutSize;
+uniform vec3 domainMin;
+uniform vec3 domainMax;
+uniform float intensity;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+vec3 srgb_from_linear(vec3 color) {
+    return mix(color * 12.92, 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055, step(vec3(0.0031308), color));
+}
+
+vec3 linear_from_srgb(vec3 color) {
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(vec3(0.04045), color));
+}
+
+void main()
+{
+    outColor = sample_color(uvs);
+    vec3 color = clamp(tone_mapping(outColor.rgb), 0.0, 1.0);
+
+    // Look up the color at the texel centers of the lookup table
+    vec3 coordinates = clamp((srgb_from_linear(color) - domainMin) / (domainMax - domainMin), 0.0, 1.0);
+    coordinates = coordinates * (lutSize - 1.0) / lutSize + 0.5 / lutSize;
+    vec3 graded = linear_from_srgb(max(texture(lutMap, coordinates).rgb, vec3(0.0)));
+
+    outColor.rgb = color_mapping(mix(color, graded, intensity));
+#ifdef USE_DEPTH
+    gl_FragDepth = sample_depth(uvs);
+#endif
+}
@@ -2,6 +2,7 @@
 uniform mat4 viewProjectionInverse;
 uniform float zNear;
 uniform float zFar;
+uniform float farDepth;
 uniform vec3 cameraPosition;
 uniform int debug_type;
 
@@ -12,7 +13,7 @@ layout (location = 0) out vec4 outColor;
 void main()
 {
     float depth = sample_depth(uvs);
-    if(depth > 0.99999)
+    if(abs(depth - farDepth) < 0.00001)
     {
         discard;
     }
@@ -25,15 +26,19 @@ void main()
     float metallic_factor = c.w;
 
     vec4 n = sample_layer(uvs, 1);
-    vec2 n2 = n.xy*2.0 - 1.0;
-    float z = 1.0 - n2.x * n2.x - n2.y * n2.y;
-    if (z > 0.0001) {
-        z = sqrt(z);
-    }
-    vec3 normal = normalize(vec3(n2.x, n2.y, (int(floor(n.z * 255.0)) & 128) == 128 ? z: -z));
+    vec3 normal = decode_normal(n);
     float roughness_factor = n.w;
-    float occlusion = float(int(floor(n.z * 255.0)) & 127) / 127.0;
-    vec3 total_emissive = sample_layer(uvs, 2).rgb;
+    float occlusion = decode_occlusion(n);
+    vec4 e = sample_layer(uvs, 2);
+    vec3 total_emissive = e.rgb;
+    vec2 specular_sheen = unpack_4bit(e.a);
+    specular_factor = specular_sheen.x;
+    sheen_roughness = specular_sheen.y;
+    vec4 l = sample_layer(uvs, 3);
+    sheen_color = l.rgb;
+    vec2 clearcoat = unpack_4bit(l.a);
+    clearcoat_factor = clearcoat.x;
+    clearcoat_roughness = clearcoat.y;
 
     if(debug_type == 0) // Position
     {
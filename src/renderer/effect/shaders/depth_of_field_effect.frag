@@ -0,0 +1,82 @@
+
+uniform vec2 resolution;
+uniform mat4 viewProjectionInverse;
+uniform mat4 view;
+uniform float farDepth;
+uniform float focusDistance;
+uniform float cocScale;
+uniform float maxBlurRadius;
+uniform int apertureBlades;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+const int SAMPLE_COUNT = 64;
+
+// The signed circle of confusion in pixels, negative in the near field and positive in the far field
+float circle_of_confusion(vec2 uv)
+{
+    float depth = sample_depth(uv);
+    if (abs(depth - farDepth) < 0.00001) {
+        return min(cocScale, maxBlurRadius);
+    }
+    float dist = max(-(view * vec4(world_pos_from_depth(viewProjectionInverse, depth, uv), 1.0)).z, 0.0001);
+    return clamp(cocScale * (dist - focusDistance) / dist, -maxBlurRadius, maxBlurRadius);
+}
+
+// Scales the radius of a point on the unit disc to the border of a regular polygon with the given number of blades
+float aperture_shape(float angle)
+{
+    if (apertureBlades < 3) {
+        return 1.0;
+    }
+    float segment = 2.0 * PI / float(apertureBlades);
+    return cos(0.5 * segment) / cos(mod(angle, segment) - 0.5 * segment);
+}
+
+float coverage(float radius, float coc)
+{
+    return clamp(coc - radius + 0.5, 0.0, 1.0);
+}
+
+void main()
+{
+    vec4 center_color = sample_color(uvs);
+    float center_coc = circle_of_confusion(uvs);
+
+    vec4 far_color = center_color;
+    float far_weight = 1.0;
+    vec4 near_color = vec4(0.0);
+    float near_weight = 0.0;
+    float near_alpha = 0.0;
+    for (int i = 0; i < SAMPLE_COUNT; i++) {
+        // Points evenly distributed on the aperture using the golden angle
+        float f = (float(i) + 0.5) / float(SAMPLE_COUNT);
+        float angle = float(i) * 2.39996323;
+        float radius = sqrt(f) * maxBlurRadius * aperture_shape(angle);
+        vec2 uv = uvs + radius * vec2(cos(angle), sin(angle)) / resolution;
+        vec4 color = sample_color(uv);
+        float coc = circle_of_confusion(uv);
+
+        // Far field: a sample only spreads onto pixels that are at least as blurred, which avoids blurred background bleeding onto sharp objects
+        float w = coverage(radius, min(max(coc, 0.0), max(center_coc, 0.0)));
+        far_color += w * color;
+        far_weight += w;
+
+        // Near field: a sample spreads onto all pixels within its circle of confusion
+        float near_coc = max(-coc, 0.0);
+        w = coverage(radius, near_coc);
+        near_color += w * color;
+        near_weight += w;
+        // Normalize by the expected number of samples within the circle of confusion to get the opacity of the near field
+        near_alpha += w * min(maxBlurRadius * maxBlurRadius / max(near_coc * near_coc, 1.0) / float(SAMPLE_COUNT), 1.0);
+    }
+    far_color /= far_weight;
+    near_color = near_weight > 0.0 ? near_color / near_weight : far_color;
+
+    outColor = mix(far_color, near_color, clamp(near_alpha, 0.0, 1.0));
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    gl_FragDepth = sample_depth(uvs);
+}
@@ -0,0 +1,79 @@
+
+#if defined(USE_NORMAL_TEXTURE)
+uniform sampler2D normalMap;
+uniform float normalThreshold;
+#elif defined(USE_NORMAL_TEXTURE_ARRAY)
+uniform sampler2DArray normalMap;
+uniform int normalLayer;
+uniform float normalThreshold;
+#endif
+
+uniform vec2 texelSize;
+uniform mat4 projectionInverse;
+uniform float farDepth;
+uniform vec4 lineColor;
+uniform float thickness;
+uniform float depthThreshold;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+// The inverse of the view space depth, which changes linearly in screen space across a plane
+float inverse_view_depth(vec2 uv)
+{
+    float depth = sample_depth(uv);
+    if (abs(depth - farDepth) < 0.00001) {
+        return 0.0;
+    }
+    return -1.0 / world_pos_from_depth(projectionInverse, depth, uv).z;
+}
+
+#if defined(USE_NORMAL_TEXTURE) || defined(USE_NORMAL_TEXTURE_ARRAY)
+vec3 normal_at(vec2 uv)
+{
+#ifdef USE_NORMAL_TEXTURE
+    return normalize(texture(normalMap, uv).xyz * 2.0 - 1.0);
+#else
+    return decode_normal(texture(normalMap, vec3(uv, normalLayer)));
+#endif
+}
+#endif
+
+void main()
+{
+#ifdef USE_COLOR
+    vec4 color = sample_color(uvs);
+#else
+    vec4 color = vec4(1.0);
+#endif
+    gl_FragDepth = sample_depth(uvs);
+
+    vec2 dx = vec2(thickness * texelSize.x, 0.0);
+    vec2 dy = vec2(0.0, thickness * texelSize.y);
+    float center = inverse_view_depth(uvs);
+    float left = inverse_view_depth(uvs - dx);
+    float right = inverse_view_depth(uvs + dx);
+    float down = inverse_view_depth(uvs - dy);
+    float up = inverse_view_depth(uvs + dy);
+
+    // The second derivative of the inverse depth is zero across a plane, so it is only large at silhouettes
+    float reference = max(max(center, max(left, right)), max(down, up));
+    float laplacian = max(abs(left + right - 2.0 * center), abs(down + up - 2.0 * center));
+    float edge = reference > 0.0 && laplacian > depthThreshold * reference ? 1.0 : 0.0;
+
+#if defined(USE_NORMAL_TEXTURE) || defined(USE_NORMAL_TEXTURE_ARRAY)
+    if (edge < 0.5 && center > 0.0) {
+        vec3 normal = normal_at(uvs);
+        vec2 offsets[4] = vec2[4](-dx, dx, -dy, dy);
+        float depths[4] = float[4](left, right, down, up);
+        for (int i = 0; i < 4; i++) {
+            if (depths[i] > 0.0 && dot(normal, normal_at(uvs + offsets[i])) < normalThreshold) {
+                edge = 1.0;
+            }
+        }
+    }
+#endif
+
+    outColor = mix(color, vec4(lineColor.rgb, 1.0), edge * lineColor.a);
+}
@@ -6,6 +6,7 @@ uniform float fogDensity;
 uniform vec4 fogColor;
 uniform float animation;
 uniform vec3 eyePosition;
+uniform float farDepth;
 
 in vec2 uvs;
 
@@ -132,7 +133,7 @@ void main()
     vec3 pos = world_pos_from_depth(viewProjectionInverse, depth, uvs);
 
     // Distance
-    float dist = depth < 0.999f ? distance(pos, eyePosition) : 100.f;
+    float dist = abs(depth - farDepth) > 0.001f ? distance(pos, eyePosition) : 100.f;
 
     float x = dist * fogDensity;
     float factor = 1. - 1. / exp(x * x);
@@ -0,0 +1,74 @@
+
+uniform mat4 viewProjectionInverse;
+uniform vec3 eyePosition;
+uniform float farDepth;
+uniform float skyDistance;
+
+uniform vec3 fogColor;
+uniform float fogDensity;
+uniform int fogMode;
+uniform float baseHeight;
+uniform float heightFalloff;
+
+#ifdef USE_SUN
+uniform vec3 sunDirection;
+uniform vec3 sunColor;
+uniform float sunExponent;
+#endif
+
+#ifdef USE_ENVIRONMENT
+uniform samplerCube irradianceMap;
+#endif
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+// The fraction of the light from the surface which reaches the camera through the fog
+float transmittance(vec3 direction, float dist)
+{
+    if (fogMode == 0) {
+        return exp(-fogDensity * dist);
+    } else if (fogMode == 1) {
+        float x = fogDensity * dist;
+        return exp(-x * x);
+    }
+
+    // Integrate the density, which decreases exponentially with the height, along the ray
+    float density = fogDensity * exp(-heightFalloff * (eyePosition.y - baseHeight));
+    float deltaHeight = heightFalloff * direction.y * dist;
+    float integral = abs(deltaHeight) > 0.0001 ? (1.0 - exp(-deltaHeight)) / deltaHeight : 1.0;
+    return exp(-density * dist * integral);
+}
+
+void main()
+{
+    vec4 color = sample_color(uvs);
+    float depth = sample_depth(uvs);
+
+    vec3 direction;
+    float dist;
+    if (abs(depth - farDepth) > 0.00001) {
+        vec3 position = world_pos_from_depth(viewProjectionInverse, depth, uvs);
+        direction = normalize(position - eyePosition);
+        dist = distance(position, eyePosition);
+    } else {
+        direction = normalize(world_pos_from_depth(viewProjectionInverse, 0.5, uvs) - eyePosition);
+        dist = skyDistance;
+    }
+
+    vec3 inScattering = fogColor;
+#ifdef USE_ENVIRONMENT
+    inScattering *= texture(irradianceMap, direction).rgb;
+#endif
+#ifdef USE_SUN
+    inScattering += sunColor * pow(max(dot(direction, -sunDirection), 0.0), sunExponent);
+#endif
+
+    // Output
+    outColor = color;
+    outColor.rgb = mix(inScattering, color.rgb, transmittance(direction, dist));
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    gl_FragDepth = depth;
+}
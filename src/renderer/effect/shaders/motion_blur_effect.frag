@@ -0,0 +1,33 @@
+
+uniform vec2 resolution;
+uniform float intensity;
+uniform int sampleCount;
+uniform float maxLength;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec2 velocity = intensity * sample_velocity(uvs);
+
+    // Limit the length of the blur in pixels
+    float length_in_pixels = length(velocity * resolution);
+    if (length_in_pixels > maxLength) {
+        velocity *= maxLength / length_in_pixels;
+    }
+
+    // Sample along the velocity centered around the current position
+    vec4 color = vec4(0.0);
+    for (int i = 0; i < sampleCount; i++) {
+        float t = sampleCount > 1 ? float(i) / float(sampleCount - 1) - 0.5 : 0.0;
+        color += sample_color(uvs - t * velocity);
+    }
+    outColor = color / float(sampleCount);
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+#ifdef USE_DEPTH
+    gl_FragDepth = sample_depth(uvs);
+#endif
+}
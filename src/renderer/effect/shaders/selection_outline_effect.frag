@@ -0,0 +1,38 @@
+
+uniform sampler2D seedMap;
+uniform sampler2D maskDepthMap;
+uniform vec2 texelSize;
+uniform int reversedDepth;
+uniform vec4 outlineColor;
+uniform float outlineWidth;
+uniform float visibleOpacity;
+uniform float occludedOpacity;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+const float DEPTH_BIAS = 0.00001;
+
+void main()
+{
+    vec4 color = sample_color(uvs);
+    outColor = color;
+    gl_FragDepth = sample_depth(uvs);
+
+    // The seed map contains the offset in pixels to the closest selected pixel, found by jump flooding the mask
+    vec2 offset = texture(seedMap, uvs).xy;
+    float closestDistance = length(offset);
+    if (closestDistance == 0.0 || closestDistance > outlineWidth) {
+        return;
+    }
+    vec2 closestUv = uvs + offset * texelSize;
+
+    // The selected object is occluded if the scene is closer to the camera than the selected object at the closest selected pixel
+    float selectionDepth = texture(maskDepthMap, closestUv).r;
+    float sceneDepth = sample_depth(closestUv);
+    bool occluded = reversedDepth == 1 ? selectionDepth < sceneDepth - DEPTH_BIAS : selectionDepth > sceneDepth + DEPTH_BIAS;
+    float opacity = occluded ? occludedOpacity : visibleOpacity;
+    float coverage = saturate(outlineWidth + 1.0 - closestDistance);
+    outColor.rgb = mix(color.rgb, outlineColor.rgb, saturate(outlineColor.a * opacity * coverage));
+}
@@ -0,0 +1,46 @@
+
+#ifdef INITIALIZE
+uniform sampler2D maskMap;
+#else
+uniform sampler2D seedMap;
+uniform vec2 texelSize;
+uniform float stepSize;
+#endif
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+// The offset used for pixels where no selected pixel has been found yet
+const float NO_SEED = 1000.0;
+
+void main()
+{
+#ifdef INITIALIZE
+    outColor = vec4(texture(maskMap, uvs).r > 0.5 ? vec2(0.0) : vec2(NO_SEED), 0.0, 1.0);
+#else
+    // Each pixel contains the offset in pixels to the closest selected pixel found so far,
+    // so look at the neighbours a step away to see if they have found a closer one
+    vec2 closestOffset = vec2(NO_SEED);
+    float closestDistance = NO_SEED;
+    for (int y = -1; y <= 1; y++) {
+        for (int x = -1; x <= 1; x++) {
+            vec2 step = vec2(x, y) * stepSize;
+            vec2 uv = uvs + step * texelSize;
+            if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+                continue;
+            }
+            vec2 offset = texture(seedMap, uv).xy;
+            if (offset.x < NO_SEED) {
+                offset += step;
+                float d = length(offset);
+                if (d < closestDistance) {
+                    closestDistance = d;
+                    closestOffset = offset;
+                }
+            }
+        }
+    }
+    outColor = vec4(closestOffset, 0.0, 1.0);
+#endif
+}
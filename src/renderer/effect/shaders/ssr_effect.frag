@@ -0,0 +1,148 @@
+
+uniform sampler2DArray geometryMap;
+uniform int geometryLayers[4];
+#ifdef USE_ENVIRONMENT
+uniform samplerCube prefilterMap;
+uniform sampler2D brdfLUT;
+uniform vec3 ambientColor;
+#endif
+
+uniform mat4 viewProjection;
+uniform mat4 viewProjectionInverse;
+uniform mat4 view;
+uniform vec3 cameraPosition;
+uniform float farDepth;
+uniform float maxDistance;
+uniform int steps;
+uniform float thickness;
+uniform float maxRoughness;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+vec3 fresnel_schlick(vec3 F0, float cosTheta)
+{
+    return F0 + (1.0 - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+vec3 fresnel_schlick_roughness(vec3 F0, float cosTheta, float roughness)
+{
+    return F0 + (max(vec3(1.0 - roughness), F0) - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+float view_depth(vec3 position)
+{
+    return -(view * vec4(position, 1.0)).z;
+}
+
+vec3 project(vec3 position)
+{
+    vec4 clip = viewProjection * vec4(position, 1.0);
+    return vec3(0.5 * clip.xy / clip.w + 0.5, clip.w);
+}
+
+float scene_view_depth(vec2 uv)
+{
+    float depth = sample_depth(uv);
+    if (abs(depth - farDepth) < 0.00001) {
+        return 1.0e30;
+    }
+    return view_depth(world_pos_from_depth(viewProjectionInverse, depth, uv));
+}
+
+bool on_screen(vec3 projected)
+{
+    return projected.z > 0.0 && all(greaterThanEqual(projected.xy, vec2(0.0))) && all(lessThanEqual(projected.xy, vec2(1.0)));
+}
+
+// Returns the screen position of the hit in xy and the confidence of the hit in z
+vec3 trace(vec3 origin, vec3 direction)
+{
+    float step_length = maxDistance / float(steps);
+    float previous = 0.0;
+    for (int i = 1; i <= steps; i++) {
+        float t = float(i) * step_length;
+        vec3 position = origin + t * direction;
+        vec3 projected = project(position);
+        if (!on_screen(projected)) {
+            return vec3(0.0);
+        }
+        float difference = view_depth(position) - scene_view_depth(projected.xy);
+        if (difference > 0.0 && difference < thickness) {
+            // Refine the hit using binary search between the previous and current step
+            float low = previous;
+            float high = t;
+            for (int j = 0; j < 6; j++) {
+                float mid = 0.5 * (low + high);
+                vec3 p = origin + mid * direction;
+                if (view_depth(p) - scene_view_depth(project(p).xy) > 0.0) {
+                    high = mid;
+                } else {
+                    low = mid;
+                }
+            }
+            vec2 uv = project(origin + high * direction).xy;
+            // Fade out towards the edges of the screen and the maximum distance
+            vec2 edge = smoothstep(vec2(0.0), vec2(0.1), uv) * (1.0 - smoothstep(vec2(0.9), vec2(1.0), uv));
+            float confidence = edge.x * edge.y * (1.0 - high / maxDistance);
+            return vec3(uv, confidence);
+        }
+        previous = t;
+    }
+    return vec3(0.0);
+}
+
+void main()
+{
+    vec4 color = sample_color(uvs);
+    float depth = sample_depth(uvs);
+    outColor = color;
+    if (abs(depth - farDepth) > 0.00001) {
+        vec3 position = world_pos_from_depth(viewProjectionInverse, depth, uvs);
+
+        vec4 c = texture(geometryMap, vec3(uvs, geometryLayers[0]));
+        vec4 n = texture(geometryMap, vec3(uvs, geometryLayers[1]));
+        vec3 normal = decode_normal(n);
+        float roughness = n.w;
+        float metallic = c.w;
+        float occlusion = decode_occlusion(n);
+        float specular_factor = unpack_4bit(texture(geometryMap, vec3(uvs, geometryLayers[2])).a).x;
+        vec2 clearcoat = unpack_4bit(texture(geometryMap, vec3(uvs, geometryLayers[3])).a);
+
+        if (min(roughness, clearcoat.x > 0.0 ? clearcoat.y : 1.0) < maxRoughness) {
+            vec3 V = normalize(cameraPosition - position);
+            vec3 R = reflect(-V, normal);
+            float NdV = max(dot(normal, V), 0.001);
+
+            // Fresnel, computed the same way as the reflection of the environment in the deferred lighting pass, see AmbientLight.
+            // The sheen is not part of the reflection of the environment, so it does not change the weight of the reflection.
+            vec3 F0 = mix(vec3(0.04) * specular_factor, c.rgb, metallic);
+            vec3 specular_fresnel = fresnel_schlick_roughness(F0, NdV, roughness);
+            vec3 clearcoat_fresnel = clearcoat.x * fresnel_schlick(vec3(0.04), NdV);
+
+            vec3 hit = trace(position + 0.01 * normal, R);
+            float gloss = 1.0 - smoothstep(0.0, maxRoughness, roughness);
+            float clearcoat_gloss = 1.0 - smoothstep(0.0, maxRoughness, clearcoat.y);
+            vec3 reflection = sample_color(hit.xy).rgb;
+#ifdef USE_ENVIRONMENT
+            // The scene color already contains the specular reflection of the environment, attenuated by the occlusion,
+            // so replace it with the reflection on the screen, using the same split-sum approximation
+            const float MAX_REFLECTION_LOD = 4.0;
+            vec3 environment = occlusion * textureLod(prefilterMap, R, roughness * MAX_REFLECTION_LOD).rgb * ambientColor;
+            vec2 brdf = texture(brdfLUT, vec2(NdV, roughness)).rg;
+            vec3 weight = (specular_fresnel * brdf.x + brdf.y) * (1.0 - clearcoat_fresnel) * gloss;
+            vec3 clearcoat_environment = occlusion * textureLod(prefilterMap, R, clearcoat.y * MAX_REFLECTION_LOD).rgb * ambientColor;
+            vec2 clearcoat_brdf = texture(brdfLUT, vec2(NdV, clearcoat.y)).rg;
+            vec3 clearcoat_weight = (clearcoat_fresnel * clearcoat_brdf.x + clearcoat_brdf.y * clearcoat.x) * clearcoat_gloss;
+            outColor.rgb += hit.z * (weight * (reflection - environment) + clearcoat_weight * (reflection - clearcoat_environment));
+            outColor.rgb = max(outColor.rgb, vec3(0.0));
+#else
+            outColor.rgb += hit.z * (specular_fresnel * (1.0 - clearcoat_fresnel) * gloss + clearcoat_fresnel * clearcoat_gloss) * reflection;
+#endif
+        }
+    }
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    gl_FragDepth = depth;
+}
@@ -0,0 +1,53 @@
+
+uniform sampler2D historyMap;
+uniform vec2 texelSize;
+uniform float blendFactor;
+uniform int reversedDepth;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+float luminance(vec3 color)
+{
+    return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+
+void main()
+{
+    vec4 current = sample_color(uvs);
+
+    // Use the velocity of the closest surface in the neighbourhood to get antialiased edges on moving objects
+    // and find the range of colors in the neighbourhood
+    vec2 closest_uv = uvs;
+    float closest_depth = sample_depth(uvs);
+    vec3 color_min = current.rgb;
+    vec3 color_max = current.rgb;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec2 uv = uvs + vec2(float(x), float(y)) * texelSize;
+            float depth = sample_depth(uv);
+            if (reversedDepth == 1 ? depth > closest_depth : depth < closest_depth) {
+                closest_depth = depth;
+                closest_uv = uv;
+            }
+            vec3 color = sample_color(uv).rgb;
+            color_min = min(color_min, color);
+            color_max = max(color_max, color);
+        }
+    }
+    vec2 velocity = sample_velocity(closest_uv);
+    vec2 history_uv = uvs - velocity;
+
+    float factor = blendFactor;
+    if (any(lessThan(history_uv, vec2(0.0))) || any(greaterThan(history_uv, vec2(1.0)))) {
+        factor = 1.0;
+    }
+    vec3 history = clamp(texture(historyMap, history_uv).rgb, color_min, color_max);
+
+    // Weigh the samples by the inverse luminance to reduce flickering of bright HDR colors
+    float current_weight = factor / (1.0 + luminance(current.rgb));
+    float history_weight = (1.0 - factor) / (1.0 + luminance(history));
+    outColor.rgb = (current.rgb * current_weight + history * history_weight) / max(current_weight + history_weight, 0.00001);
+    outColor.a = current.a;
+}
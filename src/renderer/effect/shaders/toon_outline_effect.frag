@@ -0,0 +1,47 @@
+
+uniform mat4 viewProjectionInverse;
+uniform vec3 eyePosition;
+uniform float farDepth;
+uniform vec4 outlineColor;
+uniform float outlineWidth;
+uniform float depthThreshold;
+uniform vec2 resolution;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+float view_distance(float depth, vec2 uv)
+{
+    if (abs(depth - farDepth) < 0.00001) {
+        return 1.0e30;
+    }
+    return distance(world_pos_from_depth(viewProjectionInverse, depth, uv), eyePosition);
+}
+
+void main()
+{
+    vec4 color = sample_color(uvs);
+    float depth = sample_depth(uvs);
+    float dist = view_distance(depth, uvs);
+
+    // Find the closest surface within the outline width
+    vec2 offset = outlineWidth / resolution;
+    float closest_depth = depth;
+    float closest_dist = dist;
+    for (int i = 0; i < 8; i++) {
+        float angle = float(i) * 0.25 * PI;
+        vec2 uv = uvs + offset * vec2(cos(angle), sin(angle));
+        float d = sample_depth(uv);
+        float nd = view_distance(d, uv);
+        if (nd < closest_dist) {
+            closest_dist = nd;
+            closest_depth = d;
+        }
+    }
+
+    // The pixel is part of the outline if a surface within the outline width is considerably closer to the camera
+    float factor = closest_dist < dist * (1.0 - depthThreshold) ? outlineColor.a : 0.0;
+    outColor = mix(color, vec4(outlineColor.rgb, 1.0), factor);
+    gl_FragDepth = factor > 0.0 ? closest_depth : depth;
+}
@@ -0,0 +1,159 @@
+use crate::renderer::*;
+
+///
+/// An effect that adds reflections of the rendered scene onto glossy surfaces by ray marching the depth of the scene in screen space, also called screen space reflections (SSR).
+///
+/// The effect is applied to the lit color and depth texture of a scene rendered using the [DeferredPhysicalMaterial]
+/// and uses the normals, roughness and metallic values from the geometry pass to trace the reflections.
+/// Where the reflected ray hits something on the screen, the reflection replaces the specular reflection of the environment of the [AmbientLight] used when rendering the scene, if specified,
+/// otherwise the scene is left as it is. Without an ambient light with an environment, the reflections are weighted by the Fresnel term and added to the color of the scene.
+/// The reflections are faded out with increasing roughness and applied before the tone and color mapping specified in the [Camera].
+///
+pub struct ScreenSpaceReflectionEffect<'a> {
+    /// The color texture from the geometry pass, ie. the same as given to [DeferredPhysicalMaterial::lighting_pass].
+    pub geometry_pass_color_texture: ColorTexture<'a>,
+    /// The ambient light used when rendering the scene. If it has an environment, the specular reflection of the environment, which is already part of the scene color,
+    /// is replaced by the reflection found on the screen.
+    pub ambient_light: Option<&'a AmbientLight>,
+    /// The maximum distance in world space a reflected ray is traced.
+    pub max_distance: f32,
+    /// The number of steps along the reflected ray. Higher gives more accurate reflections at the cost of performance.
+    pub steps: u32,
+    /// The thickness in world space assumed for the surfaces in the depth texture, ie. how far behind a surface the ray can be and still be counted as a hit.
+    pub thickness: f32,
+    /// The roughness above which no reflections are traced.
+    pub max_roughness: f32,
+}
+
+impl<'a> ScreenSpaceReflectionEffect<'a> {
+    ///
+    /// Constructs a new screen space reflection effect using the given geometry pass color texture and optionally the ambient light used when rendering the scene.
+    ///
+    pub fn new(
+        geometry_pass_color_texture: ColorTexture<'a>,
+        ambient_light: Option<&'a AmbientLight>,
+    ) -> Self {
+        Self {
+            geometry_pass_color_texture,
+            ambient_light,
+            max_distance: 10.0,
+            steps: 64,
+            thickness: 0.5,
+            max_roughness: 0.6,
+        }
+    }
+
+    fn environment(&self) -> Option<&Environment> {
+        self.ambient_light.and_then(|l| l.environment.as_ref())
+    }
+}
+
+impl Effect for ScreenSpaceReflectionEffect<'_> {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}",
+            include_str!("../../core/shared.frag"),
+            include_str!("../material/shaders/gbuffer.frag"),
+            if self.environment().is_some() {
+                "#define USE_ENVIRONMENT\n"
+            } else {
+                ""
+            },
+            color_texture
+                .expect("Must supply a color texture to apply a screen space reflection effect")
+                .fragment_shader_source(),
+            depth_texture
+                .expect("Must supply a depth texture to apply a screen space reflection effect")
+                .fragment_shader_source(),
+            ToneMapping::fragment_shader_source(),
+            ColorMapping::fragment_shader_source(),
+            include_str!("shaders/ssr_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 13
+            | 0b1u16 << 10
+            | if self.environment().is_some() {
+                0b1u16 << 2
+            } else {
+                0
+            }
+            | color_texture
+                .expect("Must supply a color texture to apply a screen space reflection effect")
+                .id()
+            | depth_texture
+                .expect("Must supply a depth texture to apply a screen space reflection effect")
+                .id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.color_mapping.use_uniforms(program);
+        color_texture
+            .expect("Must supply a color texture to apply a screen space reflection effect")
+            .use_uniforms(program);
+        depth_texture
+            .expect("Must supply a depth texture to apply a screen space reflection effect")
+            .use_uniforms(program);
+        if let ColorTexture::Array { texture, layers } = self.geometry_pass_color_texture {
+            let mut la: [i32; 4] = [0; 4];
+            layers
+                .iter()
+                .enumerate()
+                .for_each(|(i, l)| la[i] = *l as i32);
+            program.use_uniform_array("geometryLayers", &la);
+            program.use_texture_array("geometryMap", texture);
+        } else {
+            panic!("The geometry pass color texture must be a texture array")
+        }
+        if let Some(ambient_light) = self.ambient_light {
+            if let Some(environment) = self.environment() {
+                program.use_texture_cube("prefilterMap", &environment.prefilter_map);
+                program.use_texture("brdfLUT", &environment.brdf_map);
+            }
+            program.use_uniform_if_required(
+                "ambientColor",
+                ambient_light.color.to_linear_srgb().truncate() * ambient_light.intensity,
+            );
+        }
+        let view_projection = camera.depth_reconstruction_projection() * camera.view();
+        program.use_uniform("viewProjection", view_projection);
+        program.use_uniform("viewProjectionInverse", view_projection.invert().unwrap());
+        program.use_uniform("view", camera.view());
+        program.use_uniform("cameraPosition", camera.position());
+        program.use_uniform("farDepth", camera.depth_mapping.far_depth());
+        program.use_uniform("maxDistance", self.max_distance);
+        program.use_uniform("steps", self.steps.max(1) as i32);
+        program.use_uniform("thickness", self.thickness);
+        program.use_uniform("maxRoughness", self.max_roughness);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::renderer::*;
+use std::sync::RwLock;
+
+///
+/// An effect that reduces aliasing by accumulating the jittered frames over time, also called temporal anti-aliasing (TAA).
+///
+/// Each frame, the scene is rendered with a different sub-pixel [Camera::jitter], for example given by [TemporalAntiAliasingEffect::jitter],
+/// and blended with the result of the previous frames which is reprojected using the velocity of each pixel.
+/// To avoid ghosting, the reprojected history is clamped to the range of colors in the neighbourhood of each pixel in the current frame.
+///
+/// The effect needs the depth texture of the scene and the velocity of each pixel rendered using the [VelocityMaterial]. Either give the effect a color texture array
+/// where the first layer contains the scene color and the second layer contains the velocity, for example `ColorTexture::Array { texture: &texture, layers: &[0, 1] }`,
+/// or call [TemporalAntiAliasingEffect::render_velocity] each frame and give the effect a single color texture, which is also how the effect is used in a [PostProcessStack].
+/// Since the effect keeps the history, the same instance must be applied each frame.
+///
+pub struct TemporalAntiAliasingEffect {
+    /// The amount of the current frame blended into the history each frame. A lower value gives smoother edges but more ghosting.
+    pub blend_factor: f32,
+    history: RwLock<(Vec<Texture2D>, bool)>,
+    velocity: super::VelocityTexture,
+}
+
+impl Default for TemporalAntiAliasingEffect {
+    fn default() -> Self {
+        Self {
+            blend_factor: 0.1,
+            history: RwLock::new((Vec::new(), false)),
+            velocity: Default::default(),
+        }
+    }
+}
+
+impl TemporalAntiAliasingEffect {
+    ///
+    /// Returns the sub-pixel jitter in pixels for the given frame index, to be assigned to [Camera::jitter].
+    /// The jitter follows a Halton sequence which repeats every eight frames.
+    ///
+    pub fn jitter(frame_index: u32) -> Vec2 {
+        let halton = |mut index: u32, base: u32| {
+            let mut result = 0.0;
+            let mut fraction = 1.0;
+            while index > 0 {
+                fraction /= base as f32;
+                result += fraction * (index % base) as f32;
+                index /= base;
+            }
+            result
+        };
+        let index = frame_index % 8 + 1;
+        vec2(halton(index, 2) - 0.5, halton(index, 3) - 0.5)
+    }
+
+    ///
+    /// Renders the velocity of the given geometries as seen from the given camera into a texture owned by the effect,
+    /// which is used when the effect is applied to a single color texture.
+    /// Call this each frame before applying the effect, using the camera used for rendering the scene, including the [Camera::jitter].
+    ///
+    pub fn render_velocity(
+        &self,
+        context: &Context,
+        camera: &Camera,
+        geometries: impl IntoIterator<Item = impl Geometry>,
+    ) {
+        self.velocity.render(context, camera, geometries)
+    }
+
+    ///
+    /// Discards the accumulated history, for example when the camera is moved abruptly.
+    ///
+    pub fn reset(&self) {
+        self.history.write().unwrap().1 = false;
+    }
+}
+
+impl Effect for TemporalAntiAliasingEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        _color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}",
+            depth_texture
+                .map(|t| format!("#define USE_DEPTH\n{}", t.fragment_shader_source()))
+                .unwrap_or_default(),
+            ToneMapping::fragment_shader_source(),
+            ColorMapping::fragment_shader_source(),
+            include_str!("shaders/taa_effect.frag")
+        )
+    }
+
+    fn id(&self, _color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14 | 0b1u16 << 13 | 0b1u16 << 7 | depth_texture.map(|t| t.id()).unwrap_or(0u16)
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        _color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.color_mapping.use_uniforms(program);
+        if let Some(depth_texture) = depth_texture {
+            depth_texture.use_uniforms(program);
+        }
+        program.use_texture(
+            "historyMap",
+            self.history.read().unwrap().0.first().expect(
+                "The temporal anti-aliasing effect must be preprocessed before it is applied",
+            ),
+        );
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+
+    fn preprocess(
+        &self,
+        context: &Context,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        let color_texture = color_texture
+            .expect("Must supply a color texture to apply a temporal anti-aliasing effect");
+        let depth_texture = depth_texture
+            .expect("Must supply a depth texture to apply a temporal anti-aliasing effect");
+        let (width, height) = (color_texture.width(), color_texture.height());
+        let mut history = self.history.write().unwrap();
+        if history
+            .0
+            .first()
+            .map(|t| t.width() != width || t.height() != height)
+            .unwrap_or(true)
+        {
+            history.0 = (0..2)
+                .map(|_| {
+                    Texture2D::new_empty::<[f16; 4]>(
+                        context,
+                        width,
+                        height,
+                        Interpolation::Linear,
+                        Interpolation::Linear,
+                        None,
+                        Wrapping::ClampToEdge,
+                        Wrapping::ClampToEdge,
+                    )
+                })
+                .collect();
+            history.1 = false;
+        }
+        let has_history = history.1;
+        let mut pass_camera = camera.clone();
+        pass_camera.set_viewport(Viewport::new_at_origo(width, height));
+        let (previous, resolved) = history.0.split_at_mut(1);
+        resolved[0].as_color_target(None).apply_screen_effect(
+            &TemporalResolvePass {
+                blend_factor: if has_history {
+                    self.blend_factor.clamp(0.0, 1.0)
+                } else {
+                    1.0
+                },
+                history: &previous[0],
+                velocity: &self.velocity,
+            },
+            &pass_camera,
+            &[],
+            Some(color_texture),
+            Some(depth_texture),
+        );
+        history.0.swap(0, 1);
+        history.1 = true;
+    }
+}
+
+struct TemporalResolvePass<'a> {
+    blend_factor: f32,
+    history: &'a Texture2D,
+    velocity: &'a super::VelocityTexture,
+}
+
+impl Effect for TemporalResolvePass<'_> {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}",
+            color_texture.unwrap().fragment_shader_source(),
+            super::VelocityTexture::fragment_shader_source(color_texture, "temporal anti-aliasing"),
+            depth_texture.unwrap().fragment_shader_source(),
+            include_str!("shaders/taa_resolve.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 13
+            | 0b1u16 << 8
+            | color_texture.unwrap().id()
+            | super::VelocityTexture::id(color_texture, "temporal anti-aliasing")
+            | depth_texture.unwrap().id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        self.velocity
+            .use_uniforms(program, color_texture, "temporal anti-aliasing");
+        let color_texture = color_texture.unwrap();
+        color_texture.use_uniforms(program);
+        depth_texture.unwrap().use_uniforms(program);
+        program.use_texture("historyMap", self.history);
+        program.use_uniform(
+            "texelSize",
+            vec2(
+                1.0 / color_texture.width() as f32,
+                1.0 / color_texture.height() as f32,
+            ),
+        );
+        program.use_uniform("blendFactor", self.blend_factor);
+        program.use_uniform(
+            "reversedDepth",
+            if camera.depth_mapping.far_depth() < 0.5 {
+                1
+            } else {
+                0
+            },
+        );
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            write_mask: WriteMask::COLOR,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::renderer::*;
+
+///
+/// An effect that draws outlines around the silhouettes of the objects in the scene, for example to get a cartoon look together with a [ToonMaterial].
+/// The outlines are found where the depth changes abruptly and are drawn on top of the furthest of the two surfaces.
+/// The effect is applied to the final colors, ie. after tone and color mapping.
+///
+#[derive(Clone, Debug)]
+pub struct ToonOutlineEffect {
+    /// The color of the outline. The alpha value specifies the opacity of the outline.
+    pub color: Srgba,
+    /// The width of the outline in pixels.
+    pub width: f32,
+    /// The relative difference in distance to the camera needed for two surfaces to be separated by an outline.
+    pub depth_threshold: f32,
+}
+
+impl Default for ToonOutlineEffect {
+    fn default() -> Self {
+        Self {
+            color: Srgba::BLACK,
+            width: 2.0,
+            depth_threshold: 0.1,
+        }
+    }
+}
+
+impl Effect for ToonOutlineEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}",
+            include_str!("../../core/shared.frag"),
+            color_texture
+                .expect("Must supply a color texture to apply a toon outline effect")
+                .fragment_shader_source(),
+            depth_texture
+                .expect("Must supply a depth texture to apply a toon outline effect")
+                .fragment_shader_source(),
+            include_str!("shaders/toon_outline_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 10
+            | color_texture
+                .expect("Must supply a color texture to apply a toon outline effect")
+                .id()
+            | depth_texture
+                .expect("Must supply a depth texture to apply a toon outline effect")
+                .id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        let depth_texture =
+            depth_texture.expect("Must supply a depth texture to apply a toon outline effect");
+        color_texture
+            .expect("Must supply a color texture to apply a toon outline effect")
+            .use_uniforms(program);
+        depth_texture.use_uniforms(program);
+        program.use_uniform(
+            "resolution",
+            vec2(depth_texture.width() as f32, depth_texture.height() as f32),
+        );
+        program.use_uniform(
+            "viewProjectionInverse",
+            (camera.depth_reconstruction_projection() * camera.view())
+                .invert()
+                .unwrap(),
+        );
+        program.use_uniform("eyePosition", camera.position());
+        program.use_uniform("farDepth", camera.depth_mapping.far_depth());
+        program.use_uniform("outlineColor", Vec4::from(self.color));
+        program.use_uniform("outlineWidth", self.width);
+        program.use_uniform("depthThreshold", self.depth_threshold);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
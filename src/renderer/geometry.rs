@@ -105,6 +105,7 @@ pub use three_d_asset::{
 /// - bitangent: `out vec3 bitang;`
 /// - uv coordinates: `out vec2 uvs;` (must be flipped in v compared to standard uv coordinates, ie. do `uvs = vec2(uvs.x, 1.0 - uvs.y);` in the vertex shader or do the flip before constructing the uv coordinates vertex buffer)
 /// - color: `out vec4 col;`
+/// - previous position: `out vec3 prev_pos;` (must be in world space and use the transformation of the previous frame, or the current position if the geometry does not keep track of it, see [FragmentAttributes::velocity])
 ///
 pub trait Geometry {
     ///
@@ -142,6 +143,7 @@ pub trait Geometry {
     /// Render the geometry with the given [Effect].
     /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
     /// Use an empty array for the `lights` argument, if the material does not require lights to be rendered.
+    /// The effect is not [preprocessed](Effect::preprocess), so call that before the write method, once per frame.
     ///
     fn render_with_effect(
         &self,
@@ -354,7 +356,8 @@ impl BaseMesh {
     fn use_attributes(&self, program: &Program, attributes: FragmentAttributes) {
         program.use_vertex_attribute("position", &self.positions);
 
-        if attributes.normal {
+        // Attributes which are optimized away by the shader compiler, for example the normals when rendering a lit material without lights, are not sent
+        if attributes.normal && program.requires_attribute("normal") {
             program.use_vertex_attribute(
                 "normal",
                 self.normals.as_ref().unwrap_or_else(|| {
@@ -365,7 +368,7 @@ impl BaseMesh {
             );
         }
 
-        if attributes.tangents {
+        if attributes.tangents && program.requires_attribute("tangent") {
             program.use_vertex_attribute(
                 "tangent",
                 self.tangents.as_ref().unwrap_or_else(|| {
@@ -376,7 +379,7 @@ impl BaseMesh {
             );
         }
 
-        if attributes.uv {
+        if attributes.uv && program.requires_attribute("uv_coordinates") {
             program.use_vertex_attribute(
                 "uv_coordinates",
                 self.uvs.as_ref().unwrap_or_else(|| {
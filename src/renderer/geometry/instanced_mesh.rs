@@ -16,6 +16,7 @@ pub struct InstancedMesh {
     aabb_local: AxisAlignedBoundingBox,
     transformation: Mat4,
     current_transformation: Mat4,
+    previous_transformation: Option<Mat4>,
     animation: Option<Box<dyn Fn(f32) -> Mat4 + Send + Sync>>,
     instances: Instances,
 }
@@ -36,6 +37,7 @@ impl InstancedMesh {
             aabb_local: aabb,
             transformation: Mat4::identity(),
             current_transformation: Mat4::identity(),
+            previous_transformation: None,
             animation: None,
             instances: instances.clone(),
         };
@@ -53,12 +55,23 @@ impl InstancedMesh {
     ///
     /// Set the local to world transformation applied to all instances.
     /// This is applied before the transform for each instance.
+    /// When rendering velocities (see [FragmentAttributes::velocity]), the transformation at the time of the last call to [Self::update_previous_transformation] or [Geometry::animate] is used as the previous transformation,
+    /// so call one of these every frame before changing the transformation to get the correct motion.
     ///
     pub fn set_transformation(&mut self, transformation: Mat4) {
         self.transformation = transformation;
         self.current_transformation = transformation;
     }
 
+    ///
+    /// Stores the current transformation as the transformation of the previous frame, which is used when rendering velocities (see [FragmentAttributes::velocity]).
+    /// Call this once each frame before the transformation is changed using [Self::set_transformation].
+    /// This is also done by [Geometry::animate], so it is not needed when calling that every frame.
+    ///
+    pub fn update_previous_transformation(&mut self) {
+        self.previous_transformation = Some(self.current_transformation);
+    }
+
     ///
     /// Specifies a function which takes a time parameter as input and returns a transformation that should be applied to this mesh at the given time.
     /// To actually animate this instanced mesh, call [Geometry::animate] at each frame which in turn evaluates the animation function defined by this method.
@@ -238,6 +251,13 @@ impl Geometry for InstancedMesh {
         }
         program.use_uniform("viewProjection", camera.projection() * camera.view());
         program.use_uniform("modelMatrix", self.current_transformation);
+        if attributes.velocity {
+            program.use_uniform(
+                "previousModelMatrix",
+                self.previous_transformation
+                    .unwrap_or(self.current_transformation),
+            );
+        }
 
         for attribute_name in [
             "instance_translation",
@@ -268,7 +288,7 @@ impl Geometry for InstancedMesh {
     fn vertex_shader_source(&self, required_attributes: FragmentAttributes) -> String {
         let instance_buffers = &self.instance_buffers.read().unwrap().0;
         format!(
-            "{}{}{}{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}{}{}{}",
             if required_attributes.normal {
                 "#define USE_NORMALS\n"
             } else {
@@ -284,6 +304,11 @@ impl Geometry for InstancedMesh {
             } else {
                 ""
             },
+            if required_attributes.velocity {
+                "#define USE_VELOCITY\n"
+            } else {
+                ""
+            },
             if required_attributes.color && self.base_mesh.colors.is_some() {
                 "#define USE_VERTEX_COLORS\n"
             } else {
@@ -325,6 +350,9 @@ impl Geometry for InstancedMesh {
         if required_attributes.uv {
             id |= 0b1u16 << 2;
         }
+        if required_attributes.velocity {
+            id |= 0b1u16 << 8;
+        }
         if required_attributes.color && self.base_mesh.colors.is_some() {
             id |= 0b1u16 << 3;
         }
@@ -347,6 +375,7 @@ impl Geometry for InstancedMesh {
     }
 
     fn animate(&mut self, time: f32) {
+        self.update_previous_transformation();
         if let Some(animation) = &self.animation {
             self.current_transformation = self.transformation * animation(time);
         }
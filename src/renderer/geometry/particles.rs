@@ -78,6 +78,7 @@ pub struct ParticleSystem {
     instance_count: u32,
     transformation: Mat4,
     time: f32,
+    previous_time: f32,
 }
 
 impl ParticleSystem {
@@ -102,6 +103,7 @@ impl ParticleSystem {
             instance_count: 0,
             transformation: Mat4::identity(),
             time: 0.0,
+            previous_time: 0.0,
         };
         particles_system.set_particles(particles);
         particles_system
@@ -198,6 +200,9 @@ impl Geometry for ParticleSystem {
         if required_attributes.uv {
             id |= 0b1u16 << 2;
         }
+        if required_attributes.velocity {
+            id |= 0b1u16 << 8;
+        }
         if required_attributes.color && self.base_mesh.colors.is_some() {
             id |= 0b1u16 << 3;
         }
@@ -212,7 +217,7 @@ impl Geometry for ParticleSystem {
 
     fn vertex_shader_source(&self, required_attributes: FragmentAttributes) -> String {
         format!(
-            "#define PARTICLES\n{}{}{}{}{}{}{}{}",
+            "#define PARTICLES\n{}{}{}{}{}{}{}{}{}",
             if required_attributes.normal {
                 "#define USE_NORMALS\n"
             } else {
@@ -228,6 +233,11 @@ impl Geometry for ParticleSystem {
             } else {
                 ""
             },
+            if required_attributes.velocity {
+                "#define USE_VELOCITY\n"
+            } else {
+                ""
+            },
             if required_attributes.color && self.base_mesh.colors.is_some() {
                 "#define USE_VERTEX_COLORS\n"
             } else {
@@ -267,6 +277,10 @@ impl Geometry for ParticleSystem {
         program.use_uniform("modelMatrix", self.transformation);
         program.use_uniform("acceleration", self.acceleration);
         program.use_uniform("time", self.time);
+        if attributes.velocity {
+            program.use_uniform("previousModelMatrix", self.transformation);
+            program.use_uniform("previousTime", self.previous_time);
+        }
 
         self.base_mesh.use_attributes(program, attributes);
 
@@ -327,6 +341,7 @@ impl Geometry for ParticleSystem {
     }
 
     fn animate(&mut self, time: f32) {
+        self.previous_time = self.time;
         self.time = time;
     }
 }
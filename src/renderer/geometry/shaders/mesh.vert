@@ -1,4 +1,7 @@
 
+// The depth pre-pass relies on the position being computed exactly the same way in the depth and the shading pass
+invariant gl_Position;
+
 uniform mat4 viewProjection;
 uniform mat4 modelMatrix;
 in vec3 position;
@@ -22,6 +25,14 @@ in vec4 row3;
 
 out vec3 pos;
 
+#ifdef USE_VELOCITY
+uniform mat4 previousModelMatrix;
+#ifdef PARTICLES
+uniform float previousTime;
+#endif
+out vec3 prev_pos;
+#endif
+
 #ifdef USE_NORMALS 
 uniform mat4 normalMatrix;
 in vec3 normal;
@@ -80,6 +91,22 @@ void main()
 
     pos = worldPosition.xyz;
 
+#ifdef USE_VELOCITY
+    mat4 previousLocal2World = previousModelMatrix;
+#ifdef USE_INSTANCE_TRANSFORMS
+    previousLocal2World *= transform;
+#endif
+    vec4 previousWorldPosition = previousLocal2World * vec4(position, 1.);
+    previousWorldPosition /= previousWorldPosition.w;
+#ifdef PARTICLES
+    previousWorldPosition.xyz += start_position + start_velocity * previousTime + 0.5 * acceleration * previousTime * previousTime;
+#endif
+#ifdef USE_INSTANCE_TRANSLATIONS 
+    previousWorldPosition.xyz += instance_translation;
+#endif
+    prev_pos = previousWorldPosition.xyz;
+#endif
+
     // *** NORMAL ***
 #ifdef USE_NORMALS 
 #ifdef USE_INSTANCE_TRANSFORMS
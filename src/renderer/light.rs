@@ -40,6 +40,10 @@ mod environment;
 #[doc(inline)]
 pub use environment::*;
 
+mod ambient_occlusion;
+#[doc(inline)]
+pub use ambient_occlusion::*;
+
 use crate::core::*;
 use crate::renderer::camera::*;
 
@@ -161,7 +165,71 @@ fn shadow_matrix(camera: &Camera) -> Mat4 {
     let bias_matrix = crate::Mat4::new(
         0.5, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.5, 0.5, 0.5, 1.0,
     );
-    bias_matrix * camera.projection() * camera.view()
+    bias_matrix * camera.depth_reconstruction_projection() * camera.view()
+}
+
+///
+/// Renders the depth of the object, for example into a shadow map. Objects with an alpha cutout are rendered using their own material,
+/// wrapped so that only the depth is written, so that the cutout parts of the object do not cast shadows or occlude anything.
+///
+pub(in crate::renderer) fn render_depth(
+    context: &Context,
+    camera: &Camera,
+    object: impl crate::renderer::Object,
+    depth_material: &crate::renderer::DepthMaterial,
+) {
+    let rendered = super::with_material(&object, |geometry, material| {
+        if material.has_alpha_cutout() {
+            crate::renderer::render_with_material(
+                context,
+                camera,
+                geometry,
+                DepthPassMaterial(material),
+                &[],
+            );
+            true
+        } else {
+            false
+        }
+    });
+    if rendered != Some(true) {
+        crate::renderer::render_with_material(context, camera, &object, depth_material, &[]);
+    }
+}
+
+///
+/// Wraps the material of an object with an alpha cutout so that only the depth is written, see [render_depth].
+///
+struct DepthPassMaterial<'a>(&'a dyn crate::renderer::Material);
+
+impl crate::renderer::Material for DepthPassMaterial<'_> {
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        self.0.fragment_shader_source(lights)
+    }
+    fn fragment_attributes(&self) -> crate::renderer::FragmentAttributes {
+        self.0.fragment_attributes()
+    }
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.0.use_uniforms(program, camera, lights)
+    }
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::DEPTH,
+            ..Default::default()
+        }
+    }
+    fn material_type(&self) -> crate::renderer::MaterialType {
+        self.0.material_type()
+    }
+    fn id(&self) -> u16 {
+        self.0.id()
+    }
+    fn has_alpha_cutout(&self) -> bool {
+        self.0.has_alpha_cutout()
+    }
+    fn source_hash(&self) -> Option<u64> {
+        self.0.source_hash()
+    }
 }
 
 fn compute_up_direction(direction: Vec3) -> Vec3 {
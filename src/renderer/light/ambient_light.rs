@@ -12,6 +12,9 @@ pub struct AmbientLight {
     pub color: Srgba,
     /// The light shining from the environment. This is calculated based on an environment map.
     pub environment: Option<Environment>,
+    /// If specified, the contribution of the light is multiplied with the screen space ambient occlusion.
+    /// For forward rendering, this requires that the depth of the scene is rendered before the scene is rendered with the ambient light.
+    pub ambient_occlusion: Option<ScreenSpaceAmbientOcclusion>,
 }
 
 impl AmbientLight {
@@ -21,6 +24,7 @@ impl AmbientLight {
             intensity,
             color,
             environment: None,
+            ambient_occlusion: None,
         }
     }
 
@@ -35,13 +39,35 @@ impl AmbientLight {
             intensity,
             color,
             environment: Some(Environment::new(context, environment_map)),
+            ambient_occlusion: None,
         }
     }
 }
 
 impl Light for AmbientLight {
     fn shader_source(&self, i: u32) -> String {
-        if self.environment.is_some() {
+        let ambient_occlusion = if self.ambient_occlusion.is_some() {
+            format!(
+            "
+                uniform sampler2D ambientOcclusionMap;
+                uniform vec4 ambientOcclusionViewport;
+                float ambient_occlusion{}()
+                {{
+                    return texture(ambientOcclusionMap, (gl_FragCoord.xy - ambientOcclusionViewport.xy) / ambientOcclusionViewport.zw).r;
+                }}
+            ", i)
+        } else {
+            format!(
+                "
+                float ambient_occlusion{}()
+                {{
+                    return 1.0;
+                }}
+            ",
+                i
+            )
+        };
+        let source = if self.environment.is_some() {
             format!(
             "
                 uniform samplerCube irradianceMap;
@@ -58,7 +84,7 @@ impl Light for AmbientLight {
                     
                     // calculate reflectance at normal incidence; if dia-electric (like plastic) use F0 
                     // of 0.04 and if it's a metal, use the albedo color as F0 (metallic workflow)    
-                    vec3 F0 = mix(vec3(0.04), surface_color, metallic);
+                    vec3 F0 = mix(min(vec3(0.04) * specular_color, vec3(1.0)) * specular_factor, surface_color, metallic);
                     vec3 specular_fresnel = fresnel_schlick_roughness(F0, NdV, roughness);
                     vec3 diffuse_fresnel = 1.0 - specular_fresnel;
 
@@ -71,22 +97,35 @@ impl Light for AmbientLight {
                     vec3 prefilteredColor = textureLod(prefilterMap, R,  roughness * MAX_REFLECTION_LOD).rgb;    
                     vec2 brdf  = texture(brdfLUT, vec2(NdV, roughness)).rg;
                     vec3 specular = prefilteredColor * (specular_fresnel * brdf.x + brdf.y);
+                    vec3 result = diffuse + specular;
+
+                    // clearcoat
+                    if (clearcoat_factor > 0.0) {{
+                        vec3 Nc = dot(clearcoat_normal, clearcoat_normal) > 0.0 ? normalize(clearcoat_normal) : N;
+                        float NcdV = max(0.001, dot(Nc, V));
+                        vec3 clearcoat_fresnel = clearcoat_factor * fresnel_schlick(vec3(0.04), NcdV);
+                        vec2 clearcoat_brdf = texture(brdfLUT, vec2(NcdV, clearcoat_roughness)).rg;
+                        vec3 clearcoat_specular = textureLod(prefilterMap, reflect(-V, Nc), clearcoat_roughness * MAX_REFLECTION_LOD).rgb
+                            * (clearcoat_fresnel * clearcoat_brdf.x + clearcoat_factor * clearcoat_brdf.y);
+                        result = result * (1.0 - clearcoat_fresnel) + clearcoat_specular;
+                    }}
     
-                    return (diffuse + specular) * occlusion * ambientColor;
+                    return result * occlusion * ambient_occlusion{}() * ambientColor;
                 }}
             
-            ", i)
+            ", i, i)
         } else {
             format!(
                 "
                     uniform vec3 ambientColor;
                     vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
                     {{
-                        return occlusion * ambientColor * mix(surface_color, vec3(0.0), metallic);
+                        return occlusion * ambient_occlusion{}() * ambientColor * mix(surface_color, vec3(0.0), metallic);
                     }}
                 
-                ", i)
-        }
+                ", i, i)
+        };
+        format!("{}{}", ambient_occlusion, source)
     }
     fn use_uniforms(&self, program: &Program, _i: u32) {
         if let Some(ref environment) = self.environment {
@@ -94,6 +133,9 @@ impl Light for AmbientLight {
             program.use_texture_cube("prefilterMap", &environment.prefilter_map);
             program.use_texture("brdfLUT", &environment.brdf_map);
         }
+        if let Some(ref ambient_occlusion) = self.ambient_occlusion {
+            ambient_occlusion.use_uniforms(program);
+        }
         program.use_uniform(
             "ambientColor",
             self.color.to_linear_srgb().truncate() * self.intensity,
@@ -101,10 +143,15 @@ impl Light for AmbientLight {
     }
 
     fn id(&self) -> u8 {
-        if self.environment.is_some() {
+        let id = if self.environment.is_some() {
             0b1u8 << 7
         } else {
             0b1u8 << 7 | 0b1u8
+        };
+        if self.ambient_occlusion.is_some() {
+            id | 0b1u8 << 6
+        } else {
+            id
         }
     }
 }
@@ -115,6 +162,7 @@ impl Default for AmbientLight {
             color: Srgba::WHITE,
             intensity: 1.0,
             environment: None,
+            ambient_occlusion: None,
         }
     }
 }
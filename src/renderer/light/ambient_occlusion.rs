@@ -0,0 +1,276 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Screen space ambient occlusion (SSAO), ie. an approximation of how much of the ambient light is blocked by nearby geometry, calculated from the depth of the rendered scene.
+/// This adds contact shadows in corners and creases which are not covered by the occlusion textures of the materials.
+///
+/// Call [ScreenSpaceAmbientOcclusion::render] each frame with the depth texture of the scene and, if available, the geometry pass color texture of a [DeferredPhysicalMaterial]
+/// and then assign it to [AmbientLight::ambient_occlusion] which multiplies the contribution of the ambient light with the blurred occlusion.
+/// The occlusion is blurred without mixing the occlusion of surfaces at different depths, so the occlusion does not bleed across the edges of objects.
+/// Since the occlusion is looked up in screen space, the ambient light must be used with the same camera viewport as the one used to render the occlusion.
+///
+pub struct ScreenSpaceAmbientOcclusion {
+    context: Context,
+    /// The radius in world space of the hemisphere around each surface point in which other surfaces cause occlusion.
+    pub radius: f32,
+    /// A small distance in world space used to avoid a surface occluding itself.
+    pub bias: f32,
+    /// An exponent applied to the occlusion, a value above one gives darker occlusion.
+    pub intensity: f32,
+    /// The number of samples in the hemisphere around each surface point. Higher gives a smoother result at the cost of performance.
+    pub sample_count: u32,
+    occlusion_texture: Texture2D,
+    blurred_texture: Texture2D,
+    viewport: Viewport,
+}
+
+impl ScreenSpaceAmbientOcclusion {
+    ///
+    /// Creates a new screen space ambient occlusion with default settings.
+    /// Nothing is occluded until [ScreenSpaceAmbientOcclusion::render] is called.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            radius: 0.5,
+            bias: 0.025,
+            intensity: 1.0,
+            sample_count: 16,
+            occlusion_texture: Self::new_texture(context, 1, 1),
+            blurred_texture: Self::new_texture(context, 1, 1),
+            viewport: Viewport::new_at_origo(1, 1),
+        }
+    }
+
+    ///
+    /// Calculates the ambient occlusion as seen from the given camera.
+    /// The depth texture must contain the depth of the scene rendered with the given camera.
+    /// If the scene is rendered using a [DeferredPhysicalMaterial], the geometry pass color texture, ie. the same as given to [DeferredPhysicalMaterial::lighting_pass],
+    /// can be given to use the normals from the geometry pass, otherwise the normals are reconstructed from the depth.
+    ///
+    pub fn render(
+        &mut self,
+        camera: &Camera,
+        depth_texture: DepthTexture,
+        geometry_pass_color_texture: Option<ColorTexture>,
+    ) {
+        let (width, height) = (depth_texture.width(), depth_texture.height());
+        if self.occlusion_texture.width() != width || self.occlusion_texture.height() != height {
+            self.occlusion_texture = Self::new_texture(&self.context, width, height);
+            self.blurred_texture = Self::new_texture(&self.context, width, height);
+        }
+        self.viewport = camera.viewport();
+        let mut pass_camera = camera.clone();
+        pass_camera.set_viewport(Viewport::new_at_origo(width, height));
+        self.occlusion_texture
+            .as_color_target(None)
+            .apply_screen_effect(
+                &AmbientOcclusionPass {
+                    radius: self.radius,
+                    bias: self.bias,
+                    intensity: self.intensity,
+                    sample_count: self.sample_count,
+                },
+                &pass_camera,
+                &[],
+                geometry_pass_color_texture,
+                Some(depth_texture),
+            );
+        self.blurred_texture
+            .as_color_target(None)
+            .apply_screen_effect(
+                &AmbientOcclusionBlurPass {
+                    radius: self.radius,
+                },
+                &pass_camera,
+                &[],
+                Some(ColorTexture::Single(&self.occlusion_texture)),
+                Some(depth_texture),
+            );
+    }
+
+    ///
+    /// Returns the blurred ambient occlusion, where the red channel contains one for no occlusion and zero for fully occluded.
+    ///
+    pub fn texture(&self) -> &Texture2D {
+        &self.blurred_texture
+    }
+
+    pub(in crate::renderer) fn use_uniforms(&self, program: &Program) {
+        program.use_texture("ambientOcclusionMap", &self.blurred_texture);
+        program.use_uniform(
+            "ambientOcclusionViewport",
+            vec4(
+                self.viewport.x as f32,
+                self.viewport.y as f32,
+                self.viewport.width as f32,
+                self.viewport.height as f32,
+            ),
+        );
+    }
+
+    fn new_texture(context: &Context, width: u32, height: u32) -> Texture2D {
+        let mut texture = Texture2D::new_empty::<f16>(
+            context,
+            width,
+            height,
+            Interpolation::Linear,
+            Interpolation::Linear,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        texture
+            .as_color_target(None)
+            .clear(ClearState::color(1.0, 1.0, 1.0, 1.0));
+        texture
+    }
+}
+
+struct AmbientOcclusionPass {
+    radius: f32,
+    bias: f32,
+    intensity: f32,
+    sample_count: u32,
+}
+
+impl Effect for AmbientOcclusionPass {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}",
+            include_str!("../../core/shared.frag"),
+            include_str!("../material/shaders/gbuffer.frag"),
+            color_texture
+                .map(|t| format!("#define USE_NORMALS\n{}", t.fragment_shader_source()))
+                .unwrap_or_default(),
+            depth_texture
+                .expect("Must supply a depth texture to calculate ambient occlusion")
+                .fragment_shader_source(),
+            include_str!("shaders/ambient_occlusion.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 8
+            | color_texture.map(|t| t.id()).unwrap_or(0u16)
+            | depth_texture
+                .expect("Must supply a depth texture to calculate ambient occlusion")
+                .id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        if let Some(color_texture) = color_texture {
+            color_texture.use_uniforms(program);
+        }
+        depth_texture
+            .expect("Must supply a depth texture to calculate ambient occlusion")
+            .use_uniforms(program);
+        let view_projection = camera.depth_reconstruction_projection() * camera.view();
+        program.use_uniform("viewProjection", view_projection);
+        program.use_uniform("viewProjectionInverse", view_projection.invert().unwrap());
+        program.use_uniform("view", camera.view());
+        program.use_uniform("farDepth", camera.depth_mapping.far_depth());
+        program.use_uniform("radius", self.radius);
+        program.use_uniform("bias", self.bias);
+        program.use_uniform("intensity", self.intensity);
+        program.use_uniform("sampleCount", self.sample_count.clamp(1, 64) as i32);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            write_mask: WriteMask::COLOR,
+            ..Default::default()
+        }
+    }
+}
+
+///
+/// Blurs the ambient occlusion while preserving the edges between surfaces at different depths.
+///
+struct AmbientOcclusionBlurPass {
+    radius: f32,
+}
+
+impl Effect for AmbientOcclusionBlurPass {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}",
+            include_str!("../../core/shared.frag"),
+            color_texture.unwrap().fragment_shader_source(),
+            depth_texture.unwrap().fragment_shader_source(),
+            include_str!("shaders/ambient_occlusion_blur.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14 | 0b1u16 << 7 | color_texture.unwrap().id() | depth_texture.unwrap().id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        let color_texture = color_texture.unwrap();
+        color_texture.use_uniforms(program);
+        depth_texture.unwrap().use_uniforms(program);
+        program.use_uniform(
+            "projectionInverse",
+            camera.depth_reconstruction_projection().invert().unwrap(),
+        );
+        program.use_uniform("farDepth", camera.depth_mapping.far_depth());
+        program.use_uniform("radius", self.radius.max(0.0001));
+        program.use_uniform(
+            "texelSize",
+            vec2(
+                1.0 / color_texture.width() as f32,
+                1.0 / color_texture.height() as f32,
+            ),
+        );
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            write_mask: WriteMask::COLOR,
+            ..Default::default()
+        }
+    }
+}
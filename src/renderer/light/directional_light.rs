@@ -10,6 +10,7 @@ pub struct DirectionalLight {
     context: Context,
     shadow_texture: Option<DepthTexture2D>,
     shadow_matrix: Mat4,
+    shadow_far_depth: f32,
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
     pub intensity: f32,
     /// The base color of the light.
@@ -29,6 +30,7 @@ impl DirectionalLight {
         DirectionalLight {
             context: context.clone(),
             shadow_matrix: Mat4::identity(),
+            shadow_far_depth: 1.0,
             shadow_texture: None,
             intensity,
             color,
@@ -43,10 +45,13 @@ impl DirectionalLight {
     pub fn clear_shadow_map(&mut self) {
         self.shadow_texture = None;
         self.shadow_matrix = Mat4::identity();
+        self.shadow_far_depth = 1.0;
     }
 
     ///
     /// Generate a shadow map which is used to simulate shadows from the directional light onto the geometries given as input.
+    /// The shadow map is rendered using [DepthMapping::ReversedZ] if clip control is supported, see [Context::supports_clip_control], and otherwise using [DepthMapping::Standard],
+    /// whatever the depth mapping of the camera used for rendering the scene.
     /// It is recomended that the texture size is power of 2.
     /// If the shadows are too low resolution (the edges between shadow and non-shadow are pixelated) try to increase the texture size
     /// and/or split the scene by creating another light source with same parameters and let the two light sources shines on different parts of the scene.
@@ -55,13 +60,59 @@ impl DirectionalLight {
         &mut self,
         texture_size: u32,
         geometries: impl IntoIterator<Item = impl Geometry> + Clone,
+    ) {
+        let aabbs = geometries.clone().into_iter().map(|g| g.aabb());
+        self.render_shadow_map(
+            texture_size,
+            aabbs,
+            |context, shadow_camera, depth_material| {
+                for geometry in geometries
+                    .into_iter()
+                    .filter(|g| shadow_camera.in_frustum(&g.aabb()))
+                {
+                    render_with_material(context, shadow_camera, &geometry, depth_material, &[]);
+                }
+            },
+        );
+    }
+
+    ///
+    /// Generate a shadow map which is used to simulate shadows from the directional light onto the objects given as input.
+    /// Works like [DirectionalLight::generate_shadow_map] except that objects with a material with an alpha cutout,
+    /// see [Material::has_alpha_cutout], only cast shadows where the alpha value is above the cutout.
+    ///
+    pub fn generate_shadow_map_for_objects(
+        &mut self,
+        texture_size: u32,
+        objects: impl IntoIterator<Item = impl Object> + Clone,
+    ) {
+        let aabbs = objects.clone().into_iter().map(|o| o.aabb());
+        self.render_shadow_map(
+            texture_size,
+            aabbs,
+            |context, shadow_camera, depth_material| {
+                for object in objects
+                    .into_iter()
+                    .filter(|o| shadow_camera.in_frustum(&o.aabb()))
+                {
+                    render_depth(context, shadow_camera, object, depth_material);
+                }
+            },
+        );
+    }
+
+    fn render_shadow_map(
+        &mut self,
+        texture_size: u32,
+        aabbs: impl Iterator<Item = AxisAlignedBoundingBox>,
+        render: impl FnOnce(&Context, &Camera, &DepthMaterial),
     ) {
         let up = compute_up_direction(self.direction);
 
         let viewport = Viewport::new_at_origo(texture_size, texture_size);
         let mut aabb = AxisAlignedBoundingBox::EMPTY;
-        for geometry in geometries.clone() {
-            aabb.expand_with_aabb(&geometry.aabb());
+        for a in aabbs {
+            aabb.expand_with_aabb(&a);
         }
         if aabb.is_empty() {
             return;
@@ -71,7 +122,7 @@ impl DirectionalLight {
         let z_far = aabb.distance_max(&position);
         let z_near = aabb.distance(&position);
         let frustum_height = aabb.max().distance(aabb.min()); // TODO: more tight fit
-        let shadow_camera = Camera::new_orthographic(
+        let mut shadow_camera = Camera::new_orthographic(
             viewport,
             position,
             target,
@@ -80,6 +131,7 @@ impl DirectionalLight {
             z_near,
             z_far,
         );
+        shadow_camera.depth_mapping = DepthMapping::highest_precision(&self.context);
         let mut shadow_texture = DepthTexture2D::new::<f32>(
             &self.context,
             texture_size,
@@ -96,25 +148,19 @@ impl DirectionalLight {
         };
         shadow_texture
             .as_depth_target()
-            .clear(ClearState::default())
+            .clear(
+                shadow_camera
+                    .depth_mapping
+                    .clear_state(ClearState::default()),
+            )
             .write::<RendererError>(|| {
-                for geometry in geometries
-                    .into_iter()
-                    .filter(|g| shadow_camera.in_frustum(&g.aabb()))
-                {
-                    render_with_material(
-                        &self.context,
-                        &shadow_camera,
-                        &geometry,
-                        &depth_material,
-                        &[],
-                    );
-                }
+                render(&self.context, &shadow_camera, &depth_material);
                 Ok(())
             })
             .unwrap();
         self.shadow_texture = Some(shadow_texture);
         self.shadow_matrix = shadow_matrix(&shadow_camera);
+        self.shadow_far_depth = shadow_camera.depth_mapping.far_depth();
     }
 
     ///
@@ -132,6 +178,7 @@ impl Light for DirectionalLight {
                 "
                     uniform sampler2D shadowMap{};
                     uniform mat4 shadowMVP{};
+                    uniform float shadowFarDepth{};
 
                     uniform vec3 color{};
                     uniform vec3 direction{};
@@ -139,10 +186,10 @@ impl Light for DirectionalLight {
                     vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
                     {{
                         return calculate_light(color{}, -direction{}, surface_color, view_direction, normal, metallic, roughness)
-                            * calculate_shadow(-direction{}, normal, shadowMap{}, shadowMVP{}, position);
+                            * calculate_shadow(-direction{}, normal, shadowMap{}, shadowMVP{}, shadowFarDepth{}, position);
                     }}
 
-                ", i, i, i, i, i, i, i, i, i, i)
+                ", i, i, i, i, i, i, i, i, i, i, i, i)
         } else {
             format!(
                 "
@@ -161,6 +208,7 @@ impl Light for DirectionalLight {
         if let Some(ref tex) = self.shadow_texture {
             program.use_depth_texture(&format!("shadowMap{}", i), tex);
             program.use_uniform(&format!("shadowMVP{}", i), self.shadow_matrix);
+            program.use_uniform(&format!("shadowFarDepth{}", i), self.shadow_far_depth);
         }
         program.use_uniform(
             &format!("color{}", i),
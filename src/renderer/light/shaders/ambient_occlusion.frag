@@ -0,0 +1,84 @@
+
+uniform mat4 viewProjection;
+uniform mat4 viewProjectionInverse;
+uniform mat4 view;
+uniform float farDepth;
+uniform float radius;
+uniform float bias;
+uniform float intensity;
+uniform int sampleCount;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+vec3 position_at(vec2 uv)
+{
+    return world_pos_from_depth(viewProjectionInverse, sample_depth(uv), uv);
+}
+
+vec3 surface_normal(vec3 position)
+{
+#ifdef USE_NORMALS
+    return decode_normal(sample_layer(uvs, 1));
+#else
+    // Reconstruct the normal from the neighbouring positions, choosing the smallest difference on each axis to avoid artifacts at edges
+    vec2 texel_size = 1.0 / vec2(textureSize(depthMap, 0).xy);
+    vec3 dx0 = position - position_at(uvs - vec2(texel_size.x, 0.0));
+    vec3 dx1 = position_at(uvs + vec2(texel_size.x, 0.0)) - position;
+    vec3 dy0 = position - position_at(uvs - vec2(0.0, texel_size.y));
+    vec3 dy1 = position_at(uvs + vec2(0.0, texel_size.y)) - position;
+    vec3 dx = dot(dx0, dx0) < dot(dx1, dx1) ? dx0 : dx1;
+    vec3 dy = dot(dy0, dy0) < dot(dy1, dy1) ? dy0 : dy1;
+    vec3 normal = normalize(cross(dx, dy));
+    vec3 eye_position = (inverse(view) * vec4(0.0, 0.0, 0.0, 1.0)).xyz;
+    return dot(normal, eye_position - position) < 0.0 ? -normal : normal;
+#endif
+}
+
+float view_depth(vec3 position)
+{
+    return -(view * vec4(position, 1.0)).z;
+}
+
+void main()
+{
+    float depth = sample_depth(uvs);
+    if (abs(depth - farDepth) < 0.00001) {
+        outColor = vec4(1.0);
+        return;
+    }
+    vec3 position = world_pos_from_depth(viewProjectionInverse, depth, uvs);
+    vec3 normal = surface_normal(position);
+
+    // Rotate the sample kernel using a 4x4 pattern which is removed by the blur pass
+    ivec2 pixel = ivec2(gl_FragCoord.xy) % 4;
+    float angle = 2.0 * PI * float((pixel.x * 7 + pixel.y * 11) % 16) / 16.0;
+    vec3 up = abs(normal.y) < 0.99 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+    vec3 t = normalize(cross(up, normal));
+    vec3 b = cross(normal, t);
+    vec3 tangent = cos(angle) * t + sin(angle) * b;
+    mat3 tbn = mat3(tangent, cross(normal, tangent), normal);
+
+    float occlusion = 0.0;
+    for (int i = 0; i < sampleCount; i++) {
+        // Points on a spiral in the hemisphere, denser close to the surface point
+        float f = (float(i) + 0.5) / float(sampleCount);
+        float phi = float(i) * 2.39996323;
+        float cos_theta = sqrt(1.0 - f);
+        float sin_theta = sqrt(f);
+        vec3 direction = vec3(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+        float scale = mix(0.1, 1.0, f * f);
+        vec3 sample_position = position + tbn * direction * radius * scale;
+
+        vec4 clip = viewProjection * vec4(sample_position, 1.0);
+        vec2 sample_uv = 0.5 * clip.xy / clip.w + 0.5;
+        if (any(lessThan(sample_uv, vec2(0.0))) || any(greaterThan(sample_uv, vec2(1.0)))) {
+            continue;
+        }
+        vec3 scene_position = position_at(sample_uv);
+        float range = smoothstep(0.0, 1.0, radius / max(distance(position, scene_position), 0.00001));
+        occlusion += view_depth(scene_position) < view_depth(sample_position) - bias ? range : 0.0;
+    }
+    outColor = vec4(vec3(pow(1.0 - occlusion / float(sampleCount), intensity)), 1.0);
+}
@@ -0,0 +1,38 @@
+
+uniform vec2 texelSize;
+uniform mat4 projectionInverse;
+uniform float radius;
+uniform float farDepth;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+float view_depth(vec2 uv)
+{
+    return -world_pos_from_depth(projectionInverse, sample_depth(uv), uv).z;
+}
+
+void main()
+{
+    if (abs(sample_depth(uvs) - farDepth) < 0.00001) {
+        outColor = vec4(1.0);
+        return;
+    }
+
+    // Averages a 5x5 area where the outermost samples are given half the weight, which removes the 4x4 pattern used to rotate the samples.
+    // Samples on other surfaces than the center sample, ie. with a view depth differing by more than the occlusion radius, are ignored to avoid blurring across edges.
+    float center_depth = view_depth(uvs);
+    float occlusion = 0.0;
+    float total_weight = 0.0;
+    for (int x = -2; x <= 2; x++) {
+        for (int y = -2; y <= 2; y++) {
+            vec2 uv = uvs + vec2(float(x), float(y)) * texelSize;
+            float weight = (abs(x) == 2 ? 0.5 : 1.0) * (abs(y) == 2 ? 0.5 : 1.0);
+            weight *= max(1.0 - abs(view_depth(uv) - center_depth) / radius, 0.0);
+            occlusion += sample_color(uv).r * weight;
+            total_weight += weight;
+        }
+    }
+    outColor = vec4(vec3(occlusion / total_weight), 1.0);
+}
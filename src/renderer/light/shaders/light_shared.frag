@@ -4,6 +4,16 @@ struct BaseLight
     float intensity;
 };
 
+// Additional surface parameters used by the Cook-Torrance lighting model.
+// A material can set these before calling calculate_lighting, otherwise the defaults disable the extensions.
+float specular_factor = 1.0;
+vec3 specular_color = vec3(1.0);
+float clearcoat_factor = 0.0;
+float clearcoat_roughness = 0.0;
+vec3 clearcoat_normal = vec3(0.0);
+vec3 sheen_color = vec3(0.0);
+float sheen_roughness = 0.0;
+
 // compute fresnel specular factor
 // cosTheta could be NdV or VdH depending on used technique
 vec3 fresnel_schlick(vec3 F0, float cosTheta)
@@ -61,6 +71,21 @@ float calculate_D(float roughness, float NdH) {
     return D;
 }
 
+// Charlie sheen distribution function (Estevez and Kulla, "Production Friendly Microfacet Sheen BRDF")
+float D_charlie(in float roughness, in float NdH)
+{
+    float alpha = max(roughness * roughness, 0.0001);
+    float inv_alpha = 1.0 / alpha;
+    float sin2h = max(1.0 - NdH * NdH, 0.0078125);
+    return (2.0 + inv_alpha) * pow(sin2h, inv_alpha * 0.5) / (2.0 * PI);
+}
+
+// Neubelt and Pettineo sheen visibility function
+float V_neubelt(in float NdV, in float NdL)
+{
+    return saturate(1.0 / (4.0 * (NdL + NdV - NdL * NdV)));
+}
+
 // Smith's Schlick-GGX geometry function
 float G_schlick(in float roughness, in float NdV, in float NdL)
 {
@@ -98,15 +123,35 @@ vec3 cooktorrance_specular(in float NdL, in float NdV, in float NdH, in vec3 spe
     return specular_fresnel * G * D / (4.0 * NdV * NdL);
 }
 
+#ifdef TOON
+// Implemented by the toon material, see toon_material.frag
+vec3 calculate_toon_light(vec3 light_color, vec3 L, vec3 surface_color, vec3 V, vec3 N);
+#endif
+
+#ifdef PARALLAX_SHADOW
+// Implemented by the physical material, see physical_material.frag
+float parallax_shadow(vec3 L);
+#endif
+
 vec3 calculate_light(vec3 light_color, vec3 L, vec3 surface_color, vec3 V, vec3 N, float metallic, float roughness)
 {
+#ifdef PARALLAX_SHADOW
+    light_color *= parallax_shadow(L);
+#endif
+#ifdef TOON
+    return calculate_toon_light(light_color, L, surface_color, V, N);
+#else
     // compute material reflectance
     float NdL = max(0.001, dot(N, L));
     float NdV = max(0.001, dot(N, V));
 
     // mix between metal and non-metal material, for non-metal
     // constant base specular factor of 0.04 grey is used
+#ifdef COOK
+    vec3 F0 = mix(min(vec3(0.04) * specular_color, vec3(1.0)) * specular_factor, surface_color, metallic);
+#else
     vec3 F0 = mix(vec3(0.04), surface_color, metallic);
+#endif
 
 #ifdef PHONG
     // specular reflectance with PHONG
@@ -133,8 +178,31 @@ vec3 calculate_light(vec3 light_color, vec3 L, vec3 surface_color, vec3 V, vec3
     vec3 diffuse_fresnel = 1.0 - specular_fresnel;
     vec3 diffuse = diffuse_fresnel * mix(surface_color, vec3(0.0), metallic) / PI;
 
+#ifdef COOK
+    vec3 result = (diffuse + specular) * NdL;
+
+    // sheen
+    if (max(sheen_color.r, max(sheen_color.g, sheen_color.b)) > 0.0) {
+        result += sheen_color * D_charlie(sheen_roughness, NdH) * V_neubelt(NdV, NdL) * NdL;
+    }
+
+    // clearcoat
+    if (clearcoat_factor > 0.0) {
+        vec3 Nc = dot(clearcoat_normal, clearcoat_normal) > 0.0 ? normalize(clearcoat_normal) : N;
+        float NcdL = max(0.001, dot(Nc, L));
+        float NcdV = max(0.001, dot(Nc, V));
+        float NcdH = max(0.001, dot(Nc, H));
+        vec3 clearcoat_fresnel = clearcoat_factor * fresnel_schlick(vec3(0.04), HdV);
+        vec3 clearcoat_specular = cooktorrance_specular(NcdL, NcdV, NcdH, clearcoat_fresnel, max(clearcoat_roughness, 0.03));
+        result = result * (1.0 - clearcoat_fresnel) + clearcoat_specular * NcdL;
+    }
+
+    return result * light_color;
+#else
     // final result
     return (diffuse + specular) * light_color * NdL;
+#endif
+#endif
 }
 
 vec3 attenuate(vec3 light_color, vec3 attenuation, float distance)
@@ -146,23 +214,25 @@ vec3 attenuate(vec3 light_color, vec3 attenuation, float distance)
     return light_color / max(1.0, att);
 }
 
-float is_visible(vec3 lightDirection, vec3 normal, sampler2D shadowMap, vec4 shadow_coord, vec2 offset)
+float is_visible(vec3 lightDirection, vec3 normal, sampler2D shadowMap, vec4 shadow_coord, float far_depth, vec2 offset)
 {
     vec2 uv = (shadow_coord.xy + offset)/shadow_coord.w;
     if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
         return 1.0;
     }
     float shadow_cast_distance = texture(shadowMap, uv).x;
-    if(shadow_cast_distance > 0.999) {
+    if(abs(shadow_cast_distance - far_depth) < 0.001) {
         return 1.0;
     }
+    // The depth increases with the distance from the light when the far depth is 1 and decreases when it is 0 (reversed-Z)
+    float direction = 2.0 * far_depth - 1.0;
     // Adjust shadow bias based on surface normal and light direction
     float bias = max(0.05 * (1.0 - dot(normal, lightDirection)), 0.005);
-    float true_distance = (shadow_coord.z - bias)/shadow_coord.w;
-    return shadow_cast_distance > true_distance ? 1.0 : 0.0;
+    float true_distance = (shadow_coord.z - direction * bias)/shadow_coord.w;
+    return (shadow_cast_distance - true_distance) * direction > 0.0 ? 1.0 : 0.0;
 }
 
-float calculate_shadow(vec3 lightDirection, vec3 normal, sampler2D shadowMap, mat4 shadowMVP, vec3 position)
+float calculate_shadow(vec3 lightDirection, vec3 normal, sampler2D shadowMap, mat4 shadowMVP, float far_depth, vec3 position)
 {
     vec4 shadow_coord = shadowMVP * vec4(position, 1.);
     float visibility = 0.0;
@@ -174,7 +244,7 @@ float calculate_shadow(vec3 lightDirection, vec3 normal, sampler2D shadowMap, ma
                                  );
     for (int i=0;i<4;i++)
     {
-        visibility += is_visible(lightDirection, normal, shadowMap, shadow_coord, poissonDisk[i] * 0.001f);
+        visibility += is_visible(lightDirection, normal, shadowMap, shadow_coord, far_depth, poissonDisk[i] * 0.001f);
     }
     return visibility * 0.25;
 }
@@ -10,6 +10,7 @@ pub struct SpotLight {
     context: Context,
     shadow_texture: Option<DepthTexture2D>,
     shadow_matrix: Mat4,
+    shadow_far_depth: f32,
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
     pub intensity: f32,
     /// The base color of the light.
@@ -45,6 +46,7 @@ impl SpotLight {
             cutoff: cutoff.into(),
             attenuation,
             shadow_matrix: Mat4::identity(),
+            shadow_far_depth: 1.0,
         }
     }
 
@@ -55,10 +57,13 @@ impl SpotLight {
     pub fn clear_shadow_map(&mut self) {
         self.shadow_texture = None;
         self.shadow_matrix = Mat4::identity();
+        self.shadow_far_depth = 1.0;
     }
 
     ///
     /// Generate a shadow map which is used to simulate shadows from the spot light onto the geometries given as input.
+    /// The shadow map is rendered using [DepthMapping::ReversedZ] if clip control is supported, see [Context::supports_clip_control], and otherwise using [DepthMapping::Standard],
+    /// whatever the depth mapping of the camera used for rendering the scene.
     /// It is recomended that the texture size is power of 2.
     /// If the shadows are too low resolution (the edges between shadow and non-shadow are pixelated) try to increase the texture size.
     ///
@@ -66,6 +71,52 @@ impl SpotLight {
         &mut self,
         texture_size: u32,
         geometries: impl IntoIterator<Item = impl Geometry> + Clone,
+    ) {
+        let aabbs = geometries.clone().into_iter().map(|g| g.aabb());
+        self.render_shadow_map(
+            texture_size,
+            aabbs,
+            |context, shadow_camera, depth_material| {
+                for geometry in geometries
+                    .into_iter()
+                    .filter(|g| shadow_camera.in_frustum(&g.aabb()))
+                {
+                    render_with_material(context, shadow_camera, &geometry, depth_material, &[]);
+                }
+            },
+        );
+    }
+
+    ///
+    /// Generate a shadow map which is used to simulate shadows from the spot light onto the objects given as input.
+    /// Works like [SpotLight::generate_shadow_map] except that objects with a material with an alpha cutout,
+    /// see [Material::has_alpha_cutout], only cast shadows where the alpha value is above the cutout.
+    ///
+    pub fn generate_shadow_map_for_objects(
+        &mut self,
+        texture_size: u32,
+        objects: impl IntoIterator<Item = impl Object> + Clone,
+    ) {
+        let aabbs = objects.clone().into_iter().map(|o| o.aabb());
+        self.render_shadow_map(
+            texture_size,
+            aabbs,
+            |context, shadow_camera, depth_material| {
+                for object in objects
+                    .into_iter()
+                    .filter(|o| shadow_camera.in_frustum(&o.aabb()))
+                {
+                    render_depth(context, shadow_camera, object, depth_material);
+                }
+            },
+        );
+    }
+
+    fn render_shadow_map(
+        &mut self,
+        texture_size: u32,
+        aabbs: impl Iterator<Item = AxisAlignedBoundingBox>,
+        render: impl FnOnce(&Context, &Camera, &DepthMaterial),
     ) {
         let position = self.position;
         let direction = self.direction;
@@ -75,15 +126,14 @@ impl SpotLight {
 
         let mut z_far = 0.0f32;
         let mut z_near = f32::MAX;
-        for geometry in geometries.clone() {
-            let aabb = geometry.aabb();
+        for aabb in aabbs {
             if !aabb.is_empty() {
                 z_far = z_far.max(aabb.distance_max(&self.position));
                 z_near = z_near.min(aabb.distance(&self.position));
             }
         }
 
-        let shadow_camera = Camera::new_perspective(
+        let mut shadow_camera = Camera::new_perspective(
             viewport,
             position,
             position + direction,
@@ -92,7 +142,9 @@ impl SpotLight {
             z_near.max(0.01),
             z_far,
         );
+        shadow_camera.depth_mapping = DepthMapping::highest_precision(&self.context);
         self.shadow_matrix = shadow_matrix(&shadow_camera);
+        self.shadow_far_depth = shadow_camera.depth_mapping.far_depth();
 
         let mut shadow_texture = DepthTexture2D::new::<f32>(
             &self.context,
@@ -110,20 +162,13 @@ impl SpotLight {
         };
         shadow_texture
             .as_depth_target()
-            .clear(ClearState::default())
+            .clear(
+                shadow_camera
+                    .depth_mapping
+                    .clear_state(ClearState::default()),
+            )
             .write::<RendererError>(|| {
-                for geometry in geometries
-                    .into_iter()
-                    .filter(|g| shadow_camera.in_frustum(&g.aabb()))
-                {
-                    render_with_material(
-                        &self.context,
-                        &shadow_camera,
-                        &geometry,
-                        &depth_material,
-                        &[],
-                    );
-                }
+                render(&self.context, &shadow_camera, &depth_material);
                 Ok(())
             })
             .unwrap();
@@ -145,6 +190,7 @@ impl Light for SpotLight {
                 "
                     uniform sampler2D shadowMap{};
                     uniform mat4 shadowMVP{};
+                    uniform float shadowFarDepth{};
 
                     uniform vec3 color{};
                     uniform vec3 attenuation{};
@@ -165,12 +211,12 @@ impl Light for SpotLight {
                             vec3 light_color = attenuate(color{}, attenuation{}, distance);
                             result = calculate_light(light_color, light_direction, surface_color, view_direction, normal,
                                 metallic, roughness) * (1.0 - smoothstep(0.75 * cutoff, cutoff, angle));
-                            result *= calculate_shadow(light_direction, normal, shadowMap{}, shadowMVP{}, position);
+                            result *= calculate_shadow(light_direction, normal, shadowMap{}, shadowMVP{}, shadowFarDepth{}, position);
                         }}
                         return result;
                     }}
 
-                ", i, i, i, i, i, i, i, i, i, i, i, i, i, i, i)
+                ", i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i)
         } else {
             format!(
                 "
@@ -204,6 +250,7 @@ impl Light for SpotLight {
         if let Some(ref tex) = self.shadow_texture {
             program.use_depth_texture(&format!("shadowMap{}", i), tex);
             program.use_uniform(&format!("shadowMVP{}", i), self.shadow_matrix);
+            program.use_uniform(&format!("shadowFarDepth{}", i), self.shadow_far_depth);
         }
         program.use_uniform(
             &format!("color{}", i),
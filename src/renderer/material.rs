@@ -25,6 +25,15 @@ macro_rules! impl_material_body {
         fn id(&self) -> u16 {
             self.$inner().id()
         }
+        fn has_alpha_cutout(&self) -> bool {
+            self.$inner().has_alpha_cutout()
+        }
+        fn supports_weighted_blended(&self) -> bool {
+            self.$inner().supports_weighted_blended()
+        }
+        fn source_hash(&self) -> Option<u64> {
+            self.$inner().source_hash()
+        }
     };
 }
 
@@ -54,6 +63,10 @@ mod position_material;
 #[doc(inline)]
 pub use position_material::*;
 
+mod velocity_material;
+#[doc(inline)]
+pub use velocity_material::*;
+
 mod uv_material;
 #[doc(inline)]
 pub use uv_material::*;
@@ -74,6 +87,22 @@ mod isosurface_material;
 #[doc(inline)]
 pub use isosurface_material::*;
 
+mod matcap_material;
+#[doc(inline)]
+pub use matcap_material::*;
+
+mod terrain_material;
+#[doc(inline)]
+pub use terrain_material::*;
+
+mod toon_material;
+#[doc(inline)]
+pub use toon_material::*;
+
+mod shader_material;
+#[doc(inline)]
+pub use shader_material::*;
+
 use std::{ops::Deref, sync::Arc};
 
 ///
@@ -140,6 +169,14 @@ pub enum MaterialType {
     Transparent,
     /// Deferred opaque
     Deferred,
+    /// Forward transmissive, ie. light is transmitted through the surface, for example glass.
+    /// In a render call, for example [RenderTarget::render], transmissive objects are rendered after the opaque objects and before the transparent objects
+    /// and the already rendered opaque scene is available in the fragment shader as the color texture `uniform sampler2D sceneColorMap;`
+    /// and the depth texture `uniform sampler2D sceneDepthMap;`.
+    /// The scene color texture has mip maps and contains the final colors, ie. with tone and color mapping applied,
+    /// and the alpha value is zero where no opaque object was rendered.
+    /// Both textures have the size of the camera viewport and if the render target is multisampled, the opaque scene is rendered with the same number of samples before it is resolved into the textures.
+    Transmissive,
 }
 
 ///
@@ -158,6 +195,9 @@ pub struct FragmentAttributes {
     pub uv: bool,
     /// Color: `in vec4 col;`
     pub color: bool,
+    /// Position in world space in the previous frame: `in vec3 prev_pos;`
+    /// Geometries which do not keep track of their previous transformation output the current position.
+    pub velocity: bool,
 }
 
 impl FragmentAttributes {
@@ -168,6 +208,7 @@ impl FragmentAttributes {
         tangents: true,
         uv: true,
         color: true,
+        velocity: true,
     };
     /// No attributes
     pub const NONE: Self = Self {
@@ -176,6 +217,7 @@ impl FragmentAttributes {
         tangents: false,
         uv: false,
         color: false,
+        velocity: false,
     };
 }
 
@@ -195,6 +237,7 @@ pub trait Material {
     ///
     /// **Note:** The last bit is reserved to internally implemented materials, so if implementing the [Material] trait
     /// outside of this crate, always return an id that is smaller than `0b1u16 << 15`.
+    /// Also, the bit `0b1u16 << 13` is reserved to internal variations of a material, for example when rendering with [TransparencyMode::WeightedBlended].
     ///
     fn id(&self) -> u16;
 
@@ -218,6 +261,31 @@ pub trait Material {
     /// Returns the type of material.
     ///
     fn material_type(&self) -> MaterialType;
+
+    ///
+    /// Returns whether or not this material discards fragments based on an alpha threshold, see for example [PhysicalMaterial::alpha_cutout].
+    /// The depth of an object with such a material cannot be rendered using a [DepthMaterial], for example in a depth pre-pass (see [RenderSettings::depth_pre_pass]).
+    ///
+    fn has_alpha_cutout(&self) -> bool {
+        false
+    }
+
+    ///
+    /// Returns whether or not this material supports weighted blended order-independent transparency, see [TransparencyMode::WeightedBlended].
+    /// If true, the fragment shader must write the final color to `outColor` and then, if `WEIGHTED_BLENDED` is defined,
+    /// end the `main` function with `outColor = weighted_blended_output(outColor);` which is defined when rendering the transparent objects.
+    ///
+    fn supports_weighted_blended(&self) -> bool {
+        false
+    }
+
+    ///
+    /// Returns a hash of the shader source returned from [Material::fragment_shader_source] for materials where the [Material::id] cannot identify all variations of the source,
+    /// for example the [ShaderMaterial]. If specified, the hash is used together with the id to identify the shader program.
+    ///
+    fn source_hash(&self) -> Option<u64> {
+        None
+    }
 }
 
 ///
@@ -283,9 +351,31 @@ impl<T: Material> Material for std::sync::RwLock<T> {
     fn id(&self) -> u16 {
         self.read().unwrap().id()
     }
+    fn has_alpha_cutout(&self) -> bool {
+        self.read().unwrap().has_alpha_cutout()
+    }
+    fn supports_weighted_blended(&self) -> bool {
+        self.read().unwrap().supports_weighted_blended()
+    }
+    fn source_hash(&self) -> Option<u64> {
+        self.read().unwrap().source_hash()
+    }
+}
+
+///
+/// Returns the specular factor which scales the standard reflectance at normal incidence of 0.04 (corresponding to an index of refraction of 1.5)
+/// to the reflectance corresponding to the given index of refraction.
+///
+fn specular_from_index_of_refraction(index_of_refraction: f32) -> f32 {
+    let f0 = ((index_of_refraction - 1.0) / (index_of_refraction + 1.0)).powi(2);
+    (f0 / 0.04).min(1.0)
 }
 
 fn is_transparent(cpu_material: &CpuMaterial) -> bool {
+    cpu_material.alpha_cutout.is_none() && is_alpha_blended(cpu_material)
+}
+
+fn is_alpha_blended(cpu_material: &CpuMaterial) -> bool {
     cpu_material.albedo.a != 255
         || cpu_material
             .albedo_texture
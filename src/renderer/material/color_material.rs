@@ -16,12 +16,19 @@ pub struct ColorMaterial {
     pub render_states: RenderStates,
     /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
     pub is_transparent: bool,
+    /// A threshold on the alpha value of the color, also called alpha mask mode.
+    /// If the alpha value of a pixel touched by an object with this material is less than the threshold, then that object is not contributing to the color of that pixel.
+    /// On the other hand, if the alpha value is more than the threshold, then it is contributing fully to that pixel and thereby blocks out everything behind.
+    /// An object with an alpha cutout is rendered as an opaque object, so [Self::is_transparent] should be false.
+    /// Enable [RenderStates::alpha_to_coverage] to get smooth edges when rendering into a multisampled render target.
+    pub alpha_cutout: Option<f32>,
 }
 
 impl ColorMaterial {
     ///
     /// Constructs a new color material from a [CpuMaterial].
     /// Tries to infer whether this material is transparent or opaque from the alpha value of the albedo color and the alpha values in the albedo texture.
+    /// A material with a [CpuMaterial::alpha_cutout] is always opaque.
     /// Since this is not always correct, it is preferred to use [ColorMaterial::new_opaque] or [ColorMaterial::new_transparent].
     ///
     pub fn new(context: &Context, cpu_material: &CpuMaterial) -> Self {
@@ -51,6 +58,7 @@ impl ColorMaterial {
             texture,
             is_transparent: false,
             render_states: RenderStates::default(),
+            alpha_cutout: cpu_material.alpha_cutout,
         }
     }
 
@@ -77,6 +85,7 @@ impl ColorMaterial {
                 blend: Blend::TRANSPARENCY,
                 ..Default::default()
             },
+            alpha_cutout: cpu_material.alpha_cutout,
         }
     }
 
@@ -87,6 +96,7 @@ impl ColorMaterial {
             texture: physical_material.albedo_texture.clone(),
             render_states: physical_material.render_states,
             is_transparent: physical_material.is_transparent,
+            alpha_cutout: physical_material.alpha_cutout,
         }
     }
 }
@@ -99,11 +109,18 @@ impl FromCpuMaterial for ColorMaterial {
 
 impl Material for ColorMaterial {
     fn id(&self) -> u16 {
-        if self.texture.is_some() {
+        let mut id = if self.texture.is_some() {
             0b1u16 << 15
         } else {
             0b1u16 << 15 | 0b1u16
+        };
+        if self.alpha_cutout.is_some() {
+            id |= 0b1u16 << 11;
+            if self.render_states.alpha_to_coverage {
+                id |= 0b1u16 << 12;
+            }
         }
+        id
     }
 
     fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
@@ -111,6 +128,12 @@ impl Material for ColorMaterial {
         if self.texture.is_some() {
             shader.push_str("#define USE_TEXTURE\nin vec2 uvs;\n");
         }
+        if self.alpha_cutout.is_some() {
+            shader.push_str("#define ALPHACUT\n");
+            if self.render_states.alpha_to_coverage {
+                shader.push_str("#define ALPHA_TO_COVERAGE\n");
+            }
+        }
         shader.push_str(include_str!("../../core/shared.frag"));
         shader.push_str(ColorMapping::fragment_shader_source());
         shader.push_str(include_str!("shaders/color_material.frag"));
@@ -128,6 +151,9 @@ impl Material for ColorMaterial {
     fn use_uniforms(&self, program: &Program, camera: &Camera, _lights: &[&dyn Light]) {
         camera.color_mapping.use_uniforms(program);
         program.use_uniform("surfaceColor", self.color.to_linear_srgb());
+        if let Some(alpha_cutout) = self.alpha_cutout {
+            program.use_uniform("alphaCutout", alpha_cutout);
+        }
         if let Some(ref tex) = self.texture {
             program.use_uniform("textureTransformation", tex.transformation);
             program.use_texture("tex", tex);
@@ -143,4 +169,12 @@ impl Material for ColorMaterial {
             MaterialType::Opaque
         }
     }
+
+    fn supports_weighted_blended(&self) -> bool {
+        true
+    }
+
+    fn has_alpha_cutout(&self) -> bool {
+        self.alpha_cutout.is_some()
+    }
 }
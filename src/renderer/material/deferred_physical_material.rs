@@ -8,7 +8,7 @@ use crate::renderer::*;
 /// The first stage renders geometry information to a [RenderTarget] and the second stage uses this render target to apply lighting based on the geometry information which means the expensive lighting calculations are only done once per pixel.
 /// The [RenderTarget::render], [ColorTarget::render] or [DepthTarget::render] methods all support the two stages required by this material, so just pass the [Object] with this material applied into one of these methods.
 /// However, it is not possible to use the [Object::render] method to render a [Geometry] with this material directly to the screen.
-/// Instead render the object into a [RenderTarget] consisting of a [Texture2DArray] with three RGBA u8 layers as color target and a [DepthTexture2D] as depth target.
+/// Instead render the object into a [RenderTarget] consisting of a [Texture2DArray] with four RGBA u8 layers as color target and a [DepthTexture2D] as depth target.
 /// Then call the [DeferredPhysicalMaterial::lighting_pass] method with these textures to render to the screen.
 ///
 #[derive(Clone)]
@@ -43,10 +43,24 @@ pub struct DeferredPhysicalMaterial {
     /// Texture with color of light shining from an object.
     /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
     pub emissive_texture: Option<Texture2DRef>,
-    /// A threshold on the alpha value of the color as a workaround for transparency.
+    /// A threshold on the alpha value of the color as a workaround for transparency, also called alpha mask mode.
     /// If the alpha value of a pixel touched by an object with this material is less than the threshold, then that object is not contributing to the color of that pixel.
     /// On the other hand, if the alpha value is more than the threshold, then it is contributing fully to that pixel and thereby blocks out everything behind.
     pub alpha_cutout: Option<f32>,
+    /// A value in the range `[0..1]` specifying the strength of the specular reflection of non-metallic surfaces.
+    /// Stored with a precision of 4 bits.
+    pub specular: f32,
+    /// A value in the range `[0..1]` specifying the strength of a clear coating layer on top of the surface, for example a lacquer or car paint.
+    /// Stored with a precision of 4 bits.
+    pub clearcoat: f32,
+    /// A value in the range `[0..1]` specifying how rough the clear coating layer is.
+    /// Stored with a precision of 4 bits.
+    pub clearcoat_roughness: f32,
+    /// The color of the sheen layer, which simulates the back-scattering of cloth and fabrics. The sheen layer is disabled if the color is black.
+    pub sheen_color: Srgba,
+    /// A value in the range `[0..1]` specifying how rough the sheen layer is.
+    /// Stored with a precision of 4 bits.
+    pub sheen_roughness: f32,
 }
 
 impl DeferredPhysicalMaterial {
@@ -54,6 +68,7 @@ impl DeferredPhysicalMaterial {
     /// Constructs a new deferred physical material from a [CpuMaterial].
     /// If the input contains an [CpuMaterial::occlusion_metallic_roughness_texture], this texture is used for both
     /// [DeferredPhysicalMaterial::metallic_roughness_texture] and [DeferredPhysicalMaterial::occlusion_texture] while any [CpuMaterial::metallic_roughness_texture] or [CpuMaterial::occlusion_texture] are ignored.
+    /// The specular reflection is derived from the [CpuMaterial::index_of_refraction] while the clearcoat and sheen layers are disabled since they are not part of a [CpuMaterial].
     ///
     pub fn new(context: &Context, cpu_material: &CpuMaterial) -> Self {
         let albedo_texture =
@@ -116,11 +131,17 @@ impl DeferredPhysicalMaterial {
             alpha_cutout: cpu_material.alpha_cutout,
             emissive: cpu_material.emissive,
             emissive_texture,
+            specular: super::specular_from_index_of_refraction(cpu_material.index_of_refraction),
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.0,
+            sheen_color: Srgba::BLACK,
+            sheen_roughness: 0.0,
         }
     }
 
     ///
     /// Constructs a deferred physical material from a physical material.
+    /// The [PhysicalMaterial::specular_color] and [PhysicalMaterial::clearcoat_normal_texture] are not supported by the deferred material and are therefore ignored.
     ///
     pub fn from_physical_material(physical_material: &PhysicalMaterial) -> Self {
         Self {
@@ -142,10 +163,15 @@ impl DeferredPhysicalMaterial {
             emissive: physical_material.emissive,
             emissive_texture: physical_material.emissive_texture.clone(),
             alpha_cutout: if physical_material.is_transparent {
-                Some(0.5)
+                Some(physical_material.alpha_cutout.unwrap_or(0.5))
             } else {
-                None
+                physical_material.alpha_cutout
             },
+            specular: physical_material.specular,
+            clearcoat: physical_material.clearcoat,
+            clearcoat_roughness: physical_material.clearcoat_roughness,
+            sheen_color: physical_material.sheen_color,
+            sheen_roughness: physical_material.sheen_roughness,
         }
     }
     ///
@@ -179,6 +205,8 @@ impl FromCpuMaterial for DeferredPhysicalMaterial {
 
 impl Material for DeferredPhysicalMaterial {
     fn id(&self) -> u16 {
+        // Bit 6 identifies this material and bits 0-4 the textures. Bit 5 identifies the PhysicalMaterial, which uses bit 6 for parallax mapping,
+        // so the alpha cutout uses bit 11 instead of bit 5 to avoid sharing an id with a PhysicalMaterial with parallax mapping.
         let mut id = 0b1u16 << 15 | 0b1u16 << 6;
         if self.albedo_texture.is_some() {
             id |= 0b1u16;
@@ -196,19 +224,19 @@ impl Material for DeferredPhysicalMaterial {
             id |= 0b1u16 << 4;
         }
         if self.alpha_cutout.is_some() {
-            id |= 0b1u16 << 5;
+            id |= 0b1u16 << 11;
         }
         id
     }
 
     fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
         let mut output = include_str!("../../core/shared.frag").to_string();
+        output.push_str(include_str!("shaders/gbuffer.frag"));
         if self.albedo_texture.is_some()
             || self.metallic_roughness_texture.is_some()
             || self.normal_texture.is_some()
             || self.occlusion_texture.is_some()
             || self.emissive_texture.is_some()
-            || self.alpha_cutout.is_some()
         {
             output.push_str("in vec2 uvs;\n");
             if self.albedo_texture.is_some() {
@@ -226,15 +254,9 @@ impl Material for DeferredPhysicalMaterial {
             if self.emissive_texture.is_some() {
                 output.push_str("#define USE_EMISSIVE_TEXTURE;\n");
             }
-            if self.alpha_cutout.is_some() {
-                output.push_str(
-                    format!(
-                        "#define ALPHACUT;\nfloat acut = {};",
-                        self.alpha_cutout.unwrap()
-                    )
-                    .as_str(),
-                );
-            }
+        }
+        if self.alpha_cutout.is_some() {
+            output.push_str("#define ALPHACUT;\n");
         }
         output.push_str(include_str!("shaders/deferred_physical_material.frag"));
         output
@@ -249,9 +271,9 @@ impl Material for DeferredPhysicalMaterial {
                 || self.metallic_roughness_texture.is_some()
                 || self.normal_texture.is_some()
                 || self.occlusion_texture.is_some()
-                || self.emissive_texture.is_some()
-                || self.alpha_cutout.is_some(),
+                || self.emissive_texture.is_some(),
             tangents: self.normal_texture.is_some(),
+            velocity: false,
         }
     }
 
@@ -260,6 +282,14 @@ impl Material for DeferredPhysicalMaterial {
         program.use_uniform("roughness", self.roughness);
         program.use_uniform("albedo", self.albedo.to_linear_srgb());
         program.use_uniform("emissive", self.emissive.to_linear_srgb());
+        if let Some(alpha_cutout) = self.alpha_cutout {
+            program.use_uniform("alphaCutout", alpha_cutout);
+        }
+        program.use_uniform("specular", self.specular);
+        program.use_uniform("clearcoat", self.clearcoat);
+        program.use_uniform("clearcoatRoughness", self.clearcoat_roughness);
+        program.use_uniform("sheenColor", self.sheen_color.to_linear_srgb().truncate());
+        program.use_uniform("sheenRoughness", self.sheen_roughness);
         if let Some(ref texture) = self.albedo_texture {
             program.use_texture("albedoTexture", texture);
             program.use_uniform("albedoTexTransform", texture.transformation);
@@ -293,6 +323,10 @@ impl Material for DeferredPhysicalMaterial {
     fn material_type(&self) -> MaterialType {
         MaterialType::Deferred
     }
+
+    fn has_alpha_cutout(&self) -> bool {
+        self.alpha_cutout.is_some()
+    }
 }
 
 impl Default for DeferredPhysicalMaterial {
@@ -312,6 +346,11 @@ impl Default for DeferredPhysicalMaterial {
             alpha_cutout: None,
             emissive: Srgba::BLACK,
             emissive_texture: None,
+            specular: 1.0,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.0,
+            sheen_color: Srgba::BLACK,
+            sheen_roughness: 0.0,
         }
     }
 }
@@ -0,0 +1,122 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// A material that renders a [Geometry] using a material capture (matcap) texture, ie. an image of a lit sphere.
+/// The color of each pixel is looked up in the texture using the view space normal, so the object appears
+/// to be lit in the same way as the sphere independent of the camera view and without any lights in the scene.
+/// This material is not affected by lights and is primarily used for inspecting the shape of meshes,
+/// for example by loading a [Model] with this material.
+///
+#[derive(Clone)]
+pub struct MatcapMaterial {
+    /// A color which is multiplied with the color from the matcap texture and the per vertex colors.
+    pub color: Srgba,
+    /// A texture with an image of a lit sphere filling the entire texture.
+    /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
+    /// If `None`, a neutral clay-like sphere is used.
+    pub matcap_texture: Option<Texture2DRef>,
+    /// A scalar multiplier applied to each normal vector of the [Self::normal_texture].
+    pub normal_scale: f32,
+    /// A tangent space normal map, also known as bump map.
+    pub normal_texture: Option<Texture2DRef>,
+    /// Render states.
+    pub render_states: RenderStates,
+}
+
+impl MatcapMaterial {
+    ///
+    /// Constructs a new matcap material from a [CpuMaterial] where only the normal texture is used.
+    /// The matcap texture is the default neutral clay-like sphere, set [Self::matcap_texture] to use another one.
+    ///
+    pub fn new(context: &Context, cpu_material: &CpuMaterial) -> Self {
+        let normal_texture = cpu_material
+            .normal_texture
+            .as_ref()
+            .map(|cpu_texture| Texture2DRef::from_cpu_texture(context, cpu_texture));
+        Self {
+            normal_scale: cpu_material.normal_scale,
+            normal_texture,
+            ..Default::default()
+        }
+    }
+}
+
+impl FromCpuMaterial for MatcapMaterial {
+    fn from_cpu_material(context: &Context, cpu_material: &CpuMaterial) -> Self {
+        Self::new(context, cpu_material)
+    }
+}
+
+impl Material for MatcapMaterial {
+    fn id(&self) -> u16 {
+        let mut id = 0b1u16 << 15 | 0b1u16 << 9;
+        if self.matcap_texture.is_some() {
+            id |= 0b1u16;
+        }
+        if self.normal_texture.is_some() {
+            id |= 0b1u16 << 1;
+        }
+        id
+    }
+
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        let mut source = String::new();
+        if self.matcap_texture.is_some() {
+            source.push_str("#define USE_MATCAP_TEXTURE\n");
+        }
+        if self.normal_texture.is_some() {
+            source.push_str(
+                "#define USE_NORMAL_TEXTURE\nin vec2 uvs;\nin vec3 tang;\nin vec3 bitang;\n",
+            );
+        }
+        source.push_str(ColorMapping::fragment_shader_source());
+        source.push_str(include_str!("shaders/matcap_material.frag"));
+        source
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            position: true,
+            normal: true,
+            color: true,
+            uv: self.normal_texture.is_some(),
+            tangents: self.normal_texture.is_some(),
+            velocity: false,
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, _lights: &[&dyn Light]) {
+        camera.color_mapping.use_uniforms(program);
+        program.use_uniform("view", camera.view());
+        program.use_uniform("surfaceColor", self.color.to_linear_srgb());
+        if let Some(ref texture) = self.matcap_texture {
+            program.use_texture("matcapTexture", texture);
+        }
+        if let Some(ref texture) = self.normal_texture {
+            program.use_uniform("normalScale", self.normal_scale);
+            program.use_uniform("normalTexTransform", texture.transformation);
+            program.use_texture("normalTexture", texture);
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
+
+impl Default for MatcapMaterial {
+    fn default() -> Self {
+        Self {
+            color: Srgba::WHITE,
+            matcap_texture: None,
+            normal_scale: 1.0,
+            normal_texture: None,
+            render_states: RenderStates::default(),
+        }
+    }
+}
@@ -34,6 +34,12 @@ pub struct PhysicalMaterial {
     pub render_states: RenderStates,
     /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
     pub is_transparent: bool,
+    /// A threshold on the alpha value of the color, also called alpha mask mode.
+    /// If the alpha value of a pixel touched by an object with this material is less than the threshold, then that object is not contributing to the color of that pixel.
+    /// On the other hand, if the alpha value is more than the threshold, then it is contributing fully to that pixel and thereby blocks out everything behind.
+    /// An object with an alpha cutout is rendered as an opaque object, so [Self::is_transparent] should be false.
+    /// Enable [RenderStates::alpha_to_coverage] to get smooth edges when rendering into a multisampled render target.
+    pub alpha_cutout: Option<f32>,
     /// Color of light shining from an object.
     pub emissive: Srgba,
     /// Texture with color of light shining from an object.
@@ -41,6 +47,76 @@ pub struct PhysicalMaterial {
     pub emissive_texture: Option<Texture2DRef>,
     /// The lighting model used when rendering this material
     pub lighting_model: LightingModel,
+    /// A value in the range `[0..1]` specifying the strength of the specular reflection of non-metallic surfaces.
+    /// Only used with the [LightingModel::Cook] lighting model.
+    pub specular: f32,
+    /// A color multiplied with the specular reflection of non-metallic surfaces.
+    /// Only used with the [LightingModel::Cook] lighting model.
+    pub specular_color: Srgba,
+    /// A value in the range `[0..1]` specifying the strength of a clear coating layer on top of the surface, for example a lacquer or car paint.
+    /// Only used with the [LightingModel::Cook] lighting model.
+    pub clearcoat: f32,
+    /// A value in the range `[0..1]` specifying how rough the clear coating layer is.
+    pub clearcoat_roughness: f32,
+    /// A tangent space normal map for the clear coating layer. If `None`, the clear coating layer uses the surface normal without the [Self::normal_texture] applied.
+    pub clearcoat_normal_texture: Option<Texture2DRef>,
+    /// The color of the sheen layer, which simulates the back-scattering of cloth and fabrics. The sheen layer is disabled if the color is black.
+    /// Only used with the [LightingModel::Cook] lighting model.
+    pub sheen_color: Srgba,
+    /// A value in the range `[0..1]` specifying how rough the sheen layer is.
+    pub sheen_roughness: f32,
+    /// A value in the range `[0..1]` specifying how much light is transmitted through the surface, for example glass.
+    /// If larger than zero, this material is a [MaterialType::Transmissive] material which refracts the opaque scene behind it and should be rendered using [Blend::TRANSMISSION].
+    /// Transmission is therefore only supported when rendering in a render call, for example [RenderTarget::render].
+    pub transmission: f32,
+    /// Texture containing the transmission parameter which is multiplied with the [Self::transmission] value in the shader.
+    /// The transmission values are sampled from the red channel.
+    pub transmission_texture: Option<Texture2DRef>,
+    /// The index of refraction used when refracting light transmitted through the surface.
+    pub index_of_refraction: f32,
+    /// The thickness of the volume beneath the surface in world space units.
+    /// If zero, the surface is thin-walled, ie. the light is not refracted but only passes through the surface.
+    pub thickness: f32,
+    /// The average distance in world space units that light travels through the volume before hitting a particle.
+    /// The light is not attenuated if the distance is infinite.
+    pub attenuation_distance: f32,
+    /// The color that white light turns into after travelling the [Self::attenuation_distance] through the volume.
+    pub attenuation_color: Srgba,
+    /// A height map used for parallax occlusion mapping, ie. the uv coordinates are displaced to give the illusion of depth, for example for bricks or cobblestones.
+    /// The heights are sampled from the red channel where a value of 1.0 is the surface of the [Geometry] and 0.0 is the maximum depth given by [Self::height_scale].
+    /// Requires that the [Geometry] has uv coordinates and tangents and is not supported together with [Self::triplanar_mapping].
+    pub height_texture: Option<Texture2DRef>,
+    /// The maximum depth of the [Self::height_texture] relative to the size of the texture in uv coordinates.
+    pub height_scale: f32,
+    /// The number of steps used when searching for the intersection with the [Self::height_texture]. More steps result in higher quality and lower performance.
+    pub parallax_steps: u32,
+    /// Whether or not the [Self::height_texture] casts shadows onto itself.
+    pub parallax_self_shadowing: bool,
+    /// If `Some`, all textures are projected onto the surface along the world axes instead of using the uv coordinates of the [Geometry], see [TriplanarMapping].
+    pub triplanar_mapping: Option<TriplanarMapping>,
+}
+
+///
+/// Describes how textures are mapped onto a surface using triplanar mapping, ie. the texture is projected along each of the three world axes
+/// and the three projections are blended based on the surface normal.
+/// This is useful for geometries without uv coordinates or with stretched uv coordinates, for example a [Terrain].
+/// The normal textures are also projected, so the [Geometry] does not need to have tangents.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct TriplanarMapping {
+    /// The scale applied to the world position to get the texture coordinates, ie. the texture is repeated `scale` times per world space unit.
+    pub scale: f32,
+    /// The sharpness of the transition between the projections. A higher value results in less blending between the projections.
+    pub sharpness: f32,
+}
+
+impl Default for TriplanarMapping {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            sharpness: 4.0,
+        }
+    }
 }
 
 impl PhysicalMaterial {
@@ -48,7 +124,10 @@ impl PhysicalMaterial {
     /// Constructs a new physical material from a [CpuMaterial].
     /// If the input contains an [CpuMaterial::occlusion_metallic_roughness_texture], this texture is used for both
     /// [PhysicalMaterial::metallic_roughness_texture] and [PhysicalMaterial::occlusion_texture] while any [CpuMaterial::metallic_roughness_texture] or [CpuMaterial::occlusion_texture] are ignored.
+    /// The specular reflection is derived from the [CpuMaterial::index_of_refraction] while the clearcoat and sheen layers are disabled since they are not part of a [CpuMaterial].
+    /// If the input has a [CpuMaterial::transmission] larger than zero, the material is transmissive and the surface is thin-walled.
     /// Tries to infer whether this material is transparent or opaque from the alpha value of the albedo color and the alpha values in the albedo texture.
+    /// A material with a [CpuMaterial::alpha_cutout] is always opaque.
     /// Since this is not always correct, it is preferred to use [PhysicalMaterial::new_opaque] or [PhysicalMaterial::new_transparent].
     ///
     pub fn new(context: &Context, cpu_material: &CpuMaterial) -> Self {
@@ -115,6 +194,10 @@ impl PhysicalMaterial {
                     }
                     _ => Texture2DRef::from_cpu_texture(context, cpu_texture),
                 });
+        let transmission_texture = cpu_material
+            .transmission_texture
+            .as_ref()
+            .map(|cpu_texture| Texture2DRef::from_cpu_texture(context, cpu_texture));
         Self {
             name: cpu_material.name.clone(),
             albedo: cpu_material.albedo,
@@ -126,7 +209,12 @@ impl PhysicalMaterial {
             normal_scale: cpu_material.normal_scale,
             occlusion_texture,
             occlusion_strength: cpu_material.occlusion_strength,
-            render_states: if is_transparent {
+            render_states: if cpu_material.transmission > 0.0 {
+                RenderStates {
+                    blend: Blend::TRANSMISSION,
+                    ..Default::default()
+                }
+            } else if is_transparent {
                 RenderStates {
                     write_mask: WriteMask::COLOR,
                     blend: Blend::TRANSPARENCY,
@@ -136,11 +224,49 @@ impl PhysicalMaterial {
                 RenderStates::default()
             },
             is_transparent,
+            alpha_cutout: cpu_material.alpha_cutout,
             emissive: cpu_material.emissive,
             emissive_texture,
             lighting_model: cpu_material.lighting_model,
+            specular: super::specular_from_index_of_refraction(cpu_material.index_of_refraction),
+            specular_color: Srgba::WHITE,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.0,
+            clearcoat_normal_texture: None,
+            sheen_color: Srgba::BLACK,
+            sheen_roughness: 0.0,
+            transmission: cpu_material.transmission,
+            transmission_texture,
+            index_of_refraction: cpu_material.index_of_refraction,
+            thickness: 0.0,
+            attenuation_distance: f32::INFINITY,
+            attenuation_color: Srgba::WHITE,
+            height_texture: None,
+            height_scale: 0.05,
+            parallax_steps: 16,
+            parallax_self_shadowing: false,
+            triplanar_mapping: None,
         }
     }
+
+    fn has_transmission_texture(&self) -> bool {
+        self.transmission > 0.0 && self.transmission_texture.is_some()
+    }
+
+    fn has_parallax(&self) -> bool {
+        self.height_texture.is_some() && self.triplanar_mapping.is_none()
+    }
+
+    fn has_texture(&self) -> bool {
+        self.albedo_texture.is_some()
+            || self.metallic_roughness_texture.is_some()
+            || self.normal_texture.is_some()
+            || self.occlusion_texture.is_some()
+            || self.emissive_texture.is_some()
+            || self.clearcoat_normal_texture.is_some()
+            || self.has_transmission_texture()
+            || self.has_parallax()
+    }
 }
 
 impl FromCpuMaterial for PhysicalMaterial {
@@ -167,18 +293,52 @@ impl Material for PhysicalMaterial {
         if self.emissive_texture.is_some() {
             id |= 0b1u16 << 4;
         }
+        if self.clearcoat_normal_texture.is_some() {
+            id |= 0b1u16 << 8;
+        }
+        if self.alpha_cutout.is_some() {
+            id |= 0b1u16 << 11;
+            if self.render_states.alpha_to_coverage {
+                id |= 0b1u16 << 12;
+            }
+        }
+        if self.has_parallax() {
+            id |= 0b1u16 << 6;
+        }
+        if self.triplanar_mapping.is_some() {
+            id |= 0b1u16 << 7;
+        }
+        if self.transmission > 0.0 {
+            id |= 0b1u16 << 9;
+            if self.transmission_texture.is_some() {
+                id |= 0b1u16 << 10;
+            }
+        }
         id
     }
 
     fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
-        let mut output = lights_shader_source(lights, self.lighting_model);
-        if self.albedo_texture.is_some()
-            || self.metallic_roughness_texture.is_some()
-            || self.normal_texture.is_some()
-            || self.occlusion_texture.is_some()
-            || self.emissive_texture.is_some()
-        {
-            output.push_str("in vec2 uvs;\n");
+        let mut output = if self.has_parallax() {
+            "#define PARALLAX_SHADOW\n".to_string()
+        } else {
+            String::new()
+        };
+        output.push_str(&lights_shader_source(lights, self.lighting_model));
+        if self.transmission > 0.0 {
+            output.push_str("#define USE_TRANSMISSION;\n");
+        }
+        if self.alpha_cutout.is_some() {
+            output.push_str("#define ALPHACUT;\n");
+            if self.render_states.alpha_to_coverage {
+                output.push_str("#define ALPHA_TO_COVERAGE;\n");
+            }
+        }
+        if self.has_texture() {
+            if self.triplanar_mapping.is_some() {
+                output.push_str("#define USE_TRIPLANAR;\n");
+            } else {
+                output.push_str("#define USE_UVS;\nin vec2 uvs;\n");
+            }
             if self.albedo_texture.is_some() {
                 output.push_str("#define USE_ALBEDO_TEXTURE;\n");
             }
@@ -189,11 +349,26 @@ impl Material for PhysicalMaterial {
                 output.push_str("#define USE_OCCLUSION_TEXTURE;\n");
             }
             if self.normal_texture.is_some() {
-                output.push_str("#define USE_NORMAL_TEXTURE;\nin vec3 tang;\nin vec3 bitang;\n");
+                output.push_str("#define USE_NORMAL_TEXTURE;\n");
             }
             if self.emissive_texture.is_some() {
                 output.push_str("#define USE_EMISSIVE_TEXTURE;\n");
             }
+            if self.clearcoat_normal_texture.is_some() {
+                output.push_str("#define USE_CLEARCOAT_NORMAL_TEXTURE;\n");
+            }
+            if self.has_transmission_texture() {
+                output.push_str("#define USE_TRANSMISSION_TEXTURE;\n");
+            }
+            if self.has_parallax() {
+                output.push_str("#define USE_PARALLAX;\n");
+            }
+            if (self.normal_texture.is_some() || self.clearcoat_normal_texture.is_some())
+                && self.triplanar_mapping.is_none()
+                || self.has_parallax()
+            {
+                output.push_str("in vec3 tang;\nin vec3 bitang;\n");
+            }
         }
         output.push_str(ToneMapping::fragment_shader_source());
         output.push_str(ColorMapping::fragment_shader_source());
@@ -206,12 +381,11 @@ impl Material for PhysicalMaterial {
             position: true,
             normal: true,
             color: true,
-            uv: self.albedo_texture.is_some()
-                || self.metallic_roughness_texture.is_some()
-                || self.normal_texture.is_some()
-                || self.occlusion_texture.is_some()
-                || self.emissive_texture.is_some(),
-            tangents: self.normal_texture.is_some(),
+            uv: self.has_texture() && self.triplanar_mapping.is_none(),
+            tangents: (self.normal_texture.is_some() || self.clearcoat_normal_texture.is_some())
+                && self.triplanar_mapping.is_none()
+                || self.has_parallax(),
+            velocity: false,
         }
     }
 
@@ -225,12 +399,6 @@ impl Material for PhysicalMaterial {
             }
             program.use_uniform("metallic", self.metallic);
             program.use_uniform_if_required("roughness", self.roughness);
-            if program.requires_uniform("albedoTexture") {
-                if let Some(ref texture) = self.albedo_texture {
-                    program.use_uniform("albedoTexTransform", texture.transformation);
-                    program.use_texture("albedoTexture", texture);
-                }
-            }
             if program.requires_uniform("metallicRoughnessTexture") {
                 if let Some(ref texture) = self.metallic_roughness_texture {
                     program.use_uniform("metallicRoughnessTexTransform", texture.transformation);
@@ -251,9 +419,72 @@ impl Material for PhysicalMaterial {
                     program.use_texture("normalTexture", texture);
                 }
             }
+            program.use_uniform_if_required("specular", self.specular);
+            program.use_uniform_if_required(
+                "specularColor",
+                self.specular_color.to_linear_srgb().truncate(),
+            );
+            program.use_uniform_if_required("clearcoat", self.clearcoat);
+            program.use_uniform_if_required("clearcoatRoughness", self.clearcoat_roughness);
+            if program.requires_uniform("clearcoatNormalTexture") {
+                if let Some(ref texture) = self.clearcoat_normal_texture {
+                    program.use_uniform("clearcoatNormalTexTransform", texture.transformation);
+                    program.use_texture("clearcoatNormalTexture", texture);
+                }
+            }
+            program.use_uniform_if_required(
+                "sheenColor",
+                self.sheen_color.to_linear_srgb().truncate(),
+            );
+            program.use_uniform_if_required("sheenRoughness", self.sheen_roughness);
         }
         program.use_uniform("albedo", self.albedo.to_linear_srgb());
+        if program.requires_uniform("albedoTexture") {
+            if let Some(ref texture) = self.albedo_texture {
+                program.use_uniform("albedoTexTransform", texture.transformation);
+                program.use_texture("albedoTexture", texture);
+            }
+        }
+        if let Some(alpha_cutout) = self.alpha_cutout {
+            program.use_uniform("alphaCutout", alpha_cutout);
+        }
         program.use_uniform("emissive", self.emissive.to_linear_srgb());
+        if self.has_parallax() {
+            let texture = self.height_texture.as_ref().unwrap();
+            program.use_uniform("cameraPosition", camera.position());
+            program.use_uniform("heightTexTransform", texture.transformation);
+            program.use_uniform("heightScale", self.height_scale);
+            program.use_uniform("parallaxSteps", self.parallax_steps.max(1) as i32);
+            program.use_uniform_if_required("parallaxShadow", self.parallax_self_shadowing as i32);
+            program.use_texture("heightTexture", texture);
+        }
+        if let Some(triplanar_mapping) = self.triplanar_mapping {
+            program.use_uniform_if_required("triplanarScale", triplanar_mapping.scale);
+            program.use_uniform_if_required("triplanarSharpness", triplanar_mapping.sharpness);
+        }
+        if self.transmission > 0.0 {
+            program.use_uniform_if_required("cameraPosition", camera.position());
+            program.use_uniform(
+                "viewProjection",
+                camera.depth_reconstruction_projection() * camera.view(),
+            );
+            program.use_uniform("farDepth", camera.depth_mapping.far_depth());
+            program.use_uniform("transmission", self.transmission);
+            program.use_uniform("indexOfRefraction", self.index_of_refraction);
+            program.use_uniform("thickness", self.thickness);
+            program.use_uniform(
+                "attenuationDistance",
+                self.attenuation_distance.min(f32::MAX),
+            );
+            program.use_uniform(
+                "attenuationColor",
+                self.attenuation_color.to_linear_srgb().truncate(),
+            );
+            if let Some(ref texture) = self.transmission_texture {
+                program.use_uniform("transmissionTexTransform", texture.transformation);
+                program.use_texture("transmissionTexture", texture);
+            }
+        }
         if program.requires_uniform("emissiveTexture") {
             if let Some(ref texture) = self.emissive_texture {
                 program.use_uniform("emissiveTexTransform", texture.transformation);
@@ -266,12 +497,22 @@ impl Material for PhysicalMaterial {
         self.render_states
     }
     fn material_type(&self) -> MaterialType {
-        if self.is_transparent {
+        if self.transmission > 0.0 {
+            MaterialType::Transmissive
+        } else if self.is_transparent {
             MaterialType::Transparent
         } else {
             MaterialType::Opaque
         }
     }
+
+    fn supports_weighted_blended(&self) -> bool {
+        true
+    }
+
+    fn has_alpha_cutout(&self) -> bool {
+        self.alpha_cutout.is_some()
+    }
 }
 
 impl Default for PhysicalMaterial {
@@ -289,9 +530,28 @@ impl Default for PhysicalMaterial {
             occlusion_strength: 1.0,
             render_states: RenderStates::default(),
             is_transparent: false,
+            alpha_cutout: None,
             emissive: Srgba::BLACK,
             emissive_texture: None,
             lighting_model: LightingModel::Blinn,
+            specular: 1.0,
+            specular_color: Srgba::WHITE,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.0,
+            clearcoat_normal_texture: None,
+            sheen_color: Srgba::BLACK,
+            sheen_roughness: 0.0,
+            transmission: 0.0,
+            transmission_texture: None,
+            index_of_refraction: 1.5,
+            thickness: 0.0,
+            attenuation_distance: f32::INFINITY,
+            attenuation_color: Srgba::WHITE,
+            height_texture: None,
+            height_scale: 0.05,
+            parallax_steps: 16,
+            parallax_self_shadowing: false,
+            triplanar_mapping: None,
         }
     }
 }
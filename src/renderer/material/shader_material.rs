@@ -0,0 +1,226 @@
+use crate::core::*;
+use crate::renderer::*;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+///
+/// A uniform value or texture used by a [ShaderMaterial].
+///
+#[derive(Clone)]
+#[allow(missing_docs)]
+pub enum ShaderUniform {
+    Int(i32),
+    Float(f32),
+    Vec2(Vec2),
+    Vec3(Vec3),
+    Vec4(Vec4),
+    Mat2(Mat2),
+    Mat3(Mat3),
+    Mat4(Mat4),
+    /// A 2D texture which is available in the shader as `uniform sampler2D {name};`.
+    /// The transformation of the texture is available as `uniform mat3 {name}Transform;`.
+    Texture(Texture2DRef),
+    /// A cube map texture which is available in the shader as `uniform samplerCube {name};`.
+    TextureCube(Arc<TextureCubeMap>),
+    /// A 3D texture which is available in the shader as `uniform sampler3D {name};`.
+    Texture3D(Arc<Texture3D>),
+}
+
+macro_rules! impl_shader_uniform_from {
+    ($variant:ident, $type:ty) => {
+        impl From<$type> for ShaderUniform {
+            fn from(value: $type) -> Self {
+                Self::$variant(value)
+            }
+        }
+    };
+}
+
+impl_shader_uniform_from!(Int, i32);
+impl_shader_uniform_from!(Float, f32);
+impl_shader_uniform_from!(Vec2, Vec2);
+impl_shader_uniform_from!(Vec3, Vec3);
+impl_shader_uniform_from!(Vec4, Vec4);
+impl_shader_uniform_from!(Mat2, Mat2);
+impl_shader_uniform_from!(Mat3, Mat3);
+impl_shader_uniform_from!(Mat4, Mat4);
+impl_shader_uniform_from!(Texture, Texture2DRef);
+impl_shader_uniform_from!(TextureCube, Arc<TextureCubeMap>);
+impl_shader_uniform_from!(Texture3D, Arc<Texture3D>);
+
+///
+/// A material defined by a user-defined fragment shader and a set of named uniform values and textures.
+///
+/// The fragment shader source must contain the declarations of the uniforms, the input from the vertex shader (see [FragmentAttributes]),
+/// the output `layout (location = 0) out vec4 outColor;` and the `main` function.
+/// Depending on the arguments given to [ShaderMaterial::new], the following helpers are added in front of the source:
+/// - The lighting model: the `calculate_lighting(camera_position, surface_color, position, normal, metallic, roughness, occlusion)` function which calculates the light reflected from all lights given to the render call. The camera position is sent to the shader as `uniform vec3 cameraPosition;` if declared.
+/// - The tone mapping: the `tone_mapping(color)` function which applies the [ToneMapping] specified in the [Camera].
+/// - The color mapping: the `color_mapping(color)` function which applies the [ColorMapping] specified in the [Camera].
+///
+/// Two shader materials with the same source and helpers share the same shader program, while different sources always get different shader programs.
+/// The source and helpers are therefore fixed when the material is constructed.
+///
+#[derive(Clone)]
+pub struct ShaderMaterial {
+    source: String,
+    lighting_model: Option<LightingModel>,
+    tone_mapping: bool,
+    color_mapping: bool,
+    hash: u64,
+    /// The uniform values and textures sent to the fragment shader, indexed by their name in the shader.
+    /// Uniforms which are not used by the shader are ignored.
+    pub uniforms: HashMap<String, ShaderUniform>,
+    /// The fragment attributes, ie. the input from the vertex shader, needed by the fragment shader.
+    pub fragment_attributes: FragmentAttributes,
+    /// Render states.
+    pub render_states: RenderStates,
+    /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
+    pub is_transparent: bool,
+}
+
+impl ShaderMaterial {
+    ///
+    /// Constructs a new shader material from the given fragment shader source without any uniforms.
+    /// If a lighting model is given, the lighting helpers are added to the fragment shader using that lighting model and the lights are sent to the fragment shader.
+    /// The tone and color mapping helpers are added if `tone_mapping` and `color_mapping` are true respectively.
+    /// The material requires the position fragment attribute, ie. `in vec3 pos;`.
+    ///
+    pub fn new(
+        fragment_shader_source: impl Into<String>,
+        lighting_model: Option<LightingModel>,
+        tone_mapping: bool,
+        color_mapping: bool,
+    ) -> Self {
+        let source = fragment_shader_source.into();
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        source.hash(&mut hasher);
+        format!("{:?}", lighting_model).hash(&mut hasher);
+        tone_mapping.hash(&mut hasher);
+        color_mapping.hash(&mut hasher);
+        Self {
+            source,
+            lighting_model,
+            tone_mapping,
+            color_mapping,
+            hash: hasher.finish(),
+            uniforms: HashMap::new(),
+            fragment_attributes: FragmentAttributes {
+                position: true,
+                ..FragmentAttributes::NONE
+            },
+            render_states: RenderStates::default(),
+            is_transparent: false,
+        }
+    }
+
+    ///
+    /// Returns the fragment shader source given at construction, ie. without the helpers added by [Material::fragment_shader_source].
+    ///
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    ///
+    /// Returns the lighting model given at construction, if any.
+    ///
+    pub fn lighting_model(&self) -> Option<LightingModel> {
+        self.lighting_model
+    }
+
+    ///
+    /// Sets the uniform value or texture with the given name.
+    ///
+    pub fn set_uniform(&mut self, name: impl Into<String>, value: impl Into<ShaderUniform>) {
+        self.uniforms.insert(name.into(), value.into());
+    }
+}
+
+impl Material for ShaderMaterial {
+    fn id(&self) -> u16 {
+        // The id only has room for 13 bits of the hash, so the full hash is returned from source_hash to avoid collisions between different sources
+        0b1u16 << 15 | 0b1u16 << 14 | (self.hash & 0x1fff) as u16
+    }
+
+    fn source_hash(&self) -> Option<u64> {
+        Some(self.hash)
+    }
+
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        let mut source = match self.lighting_model {
+            Some(lighting_model) => lights_shader_source(lights, lighting_model),
+            None => include_str!("../../core/shared.frag").to_string(),
+        };
+        if self.tone_mapping {
+            source.push_str(ToneMapping::fragment_shader_source());
+        }
+        if self.color_mapping {
+            source.push_str(ColorMapping::fragment_shader_source());
+        }
+        source.push_str(&self.source);
+        source
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.fragment_attributes
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        if self.lighting_model.is_some() {
+            program.use_uniform_if_required("cameraPosition", camera.position());
+            for (i, light) in lights.iter().enumerate() {
+                light.use_uniforms(program, i as u32);
+            }
+        }
+        if self.tone_mapping {
+            camera.tone_mapping.use_uniforms(program);
+        }
+        if self.color_mapping {
+            camera.color_mapping.use_uniforms(program);
+        }
+        for (name, uniform) in self.uniforms.iter() {
+            match uniform {
+                ShaderUniform::Int(value) => program.use_uniform_if_required(name, *value),
+                ShaderUniform::Float(value) => program.use_uniform_if_required(name, *value),
+                ShaderUniform::Vec2(value) => program.use_uniform_if_required(name, *value),
+                ShaderUniform::Vec3(value) => program.use_uniform_if_required(name, *value),
+                ShaderUniform::Vec4(value) => program.use_uniform_if_required(name, *value),
+                ShaderUniform::Mat2(value) => program.use_uniform_if_required(name, *value),
+                ShaderUniform::Mat3(value) => program.use_uniform_if_required(name, *value),
+                ShaderUniform::Mat4(value) => program.use_uniform_if_required(name, *value),
+                ShaderUniform::Texture(texture) => {
+                    if program.requires_uniform(name) {
+                        program.use_texture(name, texture);
+                    }
+                    program.use_uniform_if_required(
+                        &format!("{}Transform", name),
+                        texture.transformation,
+                    );
+                }
+                ShaderUniform::TextureCube(texture) => {
+                    if program.requires_uniform(name) {
+                        program.use_texture_cube(name, texture);
+                    }
+                }
+                ShaderUniform::Texture3D(texture) => {
+                    if program.requires_uniform(name) {
+                        program.use_texture_3d(name, texture);
+                    }
+                }
+            }
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        if self.is_transparent {
+            MaterialType::Transparent
+        } else {
+            MaterialType::Opaque
+        }
+    }
+}
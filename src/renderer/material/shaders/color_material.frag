@@ -1,5 +1,9 @@
 uniform vec4 surfaceColor;
 
+#ifdef ALPHACUT
+uniform float alphaCutout;
+#endif
+
 #ifdef USE_TEXTURE
 uniform sampler2D tex;
 uniform mat3 textureTransformation;
@@ -17,5 +21,20 @@ void main()
     outColor *= texture(tex, (textureTransformation * vec3(uvs, 1.0)).xy);
     #endif
 
+    #ifdef ALPHACUT
+    #ifdef ALPHA_TO_COVERAGE
+    // Sharpen the alpha value around the cutout so that the edge is anti-aliased by the coverage
+    outColor.a = (outColor.a - alphaCutout) / max(fwidth(outColor.a), 0.0001) + 0.5;
+    if (outColor.a <= 0.0) discard;
+    #else
+    if (outColor.a < alphaCutout) discard;
+    outColor.a = 1.0;
+    #endif
+    #endif
+
     outColor.rgb = color_mapping(outColor.rgb);
-}
\ No newline at end of file
+
+#ifdef WEIGHTED_BLENDED
+    outColor = weighted_blended_output(outColor);
+#endif
+}
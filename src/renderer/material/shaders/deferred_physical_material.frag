@@ -3,6 +3,9 @@ uniform float metallic;
 uniform float roughness;
 
 uniform vec4 albedo;
+#ifdef ALPHACUT
+uniform float alphaCutout;
+#endif
 #ifdef USE_ALBEDO_TEXTURE
 uniform sampler2D albedoTexture;
 uniform mat3 albedoTexTransform;
@@ -31,6 +34,12 @@ uniform mat3 normalTexTransform;
 uniform float normalScale;
 #endif
 
+uniform float specular;
+uniform float clearcoat;
+uniform float clearcoatRoughness;
+uniform vec3 sheenColor;
+uniform float sheenRoughness;
+
 in vec3 pos;
 in vec3 nor;
 in vec4 col;
@@ -38,16 +47,17 @@ in vec4 col;
 layout (location = 0) out vec4 outColor;
 layout (location = 1) out vec4 outNormal;
 layout (location = 2) out vec4 outEmissive;
+layout (location = 3) out vec4 outLayers;
 
 void main()
 {
     vec4 surface_color = albedo * col;
 #ifdef USE_ALBEDO_TEXTURE
-    vec4 c = texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
-    #ifdef ALPHACUT
-        if (c.a < acut) discard;
-    #endif
-    surface_color *= c;
+    surface_color *= texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
+#endif
+
+#ifdef ALPHACUT
+    if (surface_color.a < alphaCutout) discard;
 #endif
 
     float metallic_factor = metallic;
@@ -83,5 +93,6 @@ void main()
         nz = 0;
     }
     outNormal = vec4(0.5 * normal.xy + 0.5, float(o | nz << 7)/255.0, roughness_factor);
-    outEmissive = vec4(total_emissive, 0.0);
+    outEmissive = vec4(total_emissive, pack_4bit(specular, sheenRoughness));
+    outLayers = vec4(sheenColor, pack_4bit(clearcoat, clearcoatRoughness));
 }
\ No newline at end of file
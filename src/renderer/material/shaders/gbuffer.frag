@@ -0,0 +1,27 @@
+
+// Packs two values in the range [0, 1] with a precision of 4 bits each into one 8 bit value.
+float pack_4bit(float high, float low)
+{
+    return float(int(round(clamp(high, 0.0, 1.0) * 15.0)) << 4 | int(round(clamp(low, 0.0, 1.0) * 15.0))) / 255.0;
+}
+
+vec2 unpack_4bit(float value)
+{
+    int v = int(round(value * 255.0));
+    return vec2(float(v >> 4), float(v & 15)) / 15.0;
+}
+
+// Decodes the normal from the normal layer of the geometry buffer, where the x and y components of the normal are stored in the x and y components
+// and the sign of the z component is stored in the highest bit of the z component.
+vec3 decode_normal(vec4 n)
+{
+    vec2 n2 = n.xy * 2.0 - 1.0;
+    float z = sqrt(max(1.0 - n2.x * n2.x - n2.y * n2.y, 0.0));
+    return normalize(vec3(n2.x, n2.y, (int(floor(n.z * 255.0)) & 128) == 128 ? z : -z));
+}
+
+// Decodes the occlusion from the normal layer of the geometry buffer, which is stored in the lowest 7 bits of the z component.
+float decode_occlusion(vec4 n)
+{
+    return float(int(floor(n.z * 255.0)) & 127) / 127.0;
+}
@@ -0,0 +1,51 @@
+uniform mat4 view;
+uniform vec4 surfaceColor;
+
+#ifdef USE_MATCAP_TEXTURE
+uniform sampler2D matcapTexture;
+#endif
+
+#ifdef USE_NORMAL_TEXTURE
+uniform sampler2D normalTexture;
+uniform mat3 normalTexTransform;
+uniform float normalScale;
+#endif
+
+in vec3 pos;
+in vec3 nor;
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+#ifdef USE_NORMAL_TEXTURE
+    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
+    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+    normal = normalize(tbn * ((2.0 * texture(normalTexture, (normalTexTransform * vec3(uvs, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0)));
+#endif
+
+    // Project the view space normal onto a plane perpendicular to the view direction
+    vec3 view_normal = normalize(mat3(view) * normal);
+    vec3 view_direction = normalize((view * vec4(pos, 1.0)).xyz);
+    vec3 x = normalize(vec3(-view_direction.z, 0.0, view_direction.x));
+    vec3 y = cross(x, view_direction);
+    vec2 uv = vec2(dot(x, view_normal), dot(y, view_normal)) * 0.495 + 0.5;
+
+#ifdef USE_MATCAP_TEXTURE
+    vec4 matcap = texture(matcapTexture, uv);
+#else
+    // A neutral clay-like sphere lit from the upper left
+    vec3 sphere_normal = vec3(2.0 * uv - 1.0, 0.0);
+    sphere_normal.z = sqrt(max(1.0 - dot(sphere_normal.xy, sphere_normal.xy), 0.0));
+    vec3 light_direction = normalize(vec3(-0.5, 0.7, 0.6));
+    float diffuse = 0.25 + 0.65 * max(dot(sphere_normal, light_direction), 0.0);
+    float specular = 0.2 * pow(max(dot(reflect(-light_direction, sphere_normal), vec3(0.0, 0.0, 1.0)), 0.0), 16.0);
+    vec4 matcap = vec4(vec3(diffuse + specular), 1.0);
+#endif
+
+    outColor = surfaceColor * col * matcap;
+    outColor.rgb = color_mapping(outColor.rgb);
+}
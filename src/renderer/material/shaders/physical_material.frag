@@ -4,6 +4,9 @@ uniform float roughness;
 uniform vec3 cameraPosition;
 
 uniform vec4 albedo;
+#ifdef ALPHACUT
+uniform float alphaCutout;
+#endif
 #ifdef USE_ALBEDO_TEXTURE
 uniform sampler2D albedoTexture;
 uniform mat3 albedoTexTransform;
@@ -32,51 +35,271 @@ uniform mat3 normalTexTransform;
 uniform float normalScale;
 #endif
 
+uniform float specular;
+uniform vec3 specularColor;
+uniform float clearcoat;
+uniform float clearcoatRoughness;
+#ifdef USE_CLEARCOAT_NORMAL_TEXTURE
+uniform sampler2D clearcoatNormalTexture;
+uniform mat3 clearcoatNormalTexTransform;
+#endif
+uniform vec3 sheenColor;
+uniform float sheenRoughness;
+
+#ifdef USE_TRANSMISSION
+uniform mat4 viewProjection;
+uniform float farDepth;
+uniform float transmission;
+uniform float indexOfRefraction;
+uniform float thickness;
+uniform float attenuationDistance;
+uniform vec3 attenuationColor;
+uniform sampler2D sceneColorMap;
+uniform sampler2D sceneDepthMap;
+#ifdef USE_TRANSMISSION_TEXTURE
+uniform sampler2D transmissionTexture;
+uniform mat3 transmissionTexTransform;
+#endif
+
+vec2 screen_uv(vec3 position)
+{
+    vec4 p = viewProjection * vec4(position, 1.0);
+    return 0.5 * p.xy / p.w + 0.5;
+}
+#endif
+
 in vec3 pos;
 in vec3 nor;
 in vec4 col;
 
 layout (location = 0) out vec4 outColor;
 
+#ifdef USE_TRIPLANAR
+uniform float triplanarScale;
+uniform float triplanarSharpness;
+
+vec3 triplanar_weights;
+vec3 triplanar_sign;
+vec2 triplanar_uv_x;
+vec2 triplanar_uv_y;
+vec2 triplanar_uv_z;
+
+void init_triplanar()
+{
+    vec3 n = normalize(gl_FrontFacing ? nor : -nor);
+    triplanar_weights = pow(abs(n), vec3(triplanarSharpness));
+    triplanar_weights /= triplanar_weights.x + triplanar_weights.y + triplanar_weights.z;
+    triplanar_sign = vec3(n.x < 0.0 ? -1.0 : 1.0, n.y < 0.0 ? -1.0 : 1.0, n.z < 0.0 ? -1.0 : 1.0);
+    // Mirror the uv coordinates on the negative sides to avoid mirrored textures
+    vec3 p = pos * triplanarScale;
+    triplanar_uv_x = vec2(-p.z * triplanar_sign.x, p.y);
+    triplanar_uv_y = vec2(p.x * triplanar_sign.y, -p.z);
+    triplanar_uv_z = vec2(p.x * triplanar_sign.z, p.y);
+}
+
+vec4 sample_texture(sampler2D tex, mat3 transform)
+{
+    return texture(tex, (transform * vec3(triplanar_uv_x, 1.0)).xy) * triplanar_weights.x
+        + texture(tex, (transform * vec3(triplanar_uv_y, 1.0)).xy) * triplanar_weights.y
+        + texture(tex, (transform * vec3(triplanar_uv_z, 1.0)).xy) * triplanar_weights.z;
+}
+
+// Blends the tangent space normals of the three projections using a whiteout blend
+vec3 sample_normal_texture(sampler2D tex, mat3 transform, vec3 normal, float scale)
+{
+    vec3 scale_xy = vec3(scale, scale, 1.0);
+    vec3 tnormal_x = (2.0 * texture(tex, (transform * vec3(triplanar_uv_x, 1.0)).xy).xyz - 1.0) * scale_xy;
+    vec3 tnormal_y = (2.0 * texture(tex, (transform * vec3(triplanar_uv_y, 1.0)).xy).xyz - 1.0) * scale_xy;
+    vec3 tnormal_z = (2.0 * texture(tex, (transform * vec3(triplanar_uv_z, 1.0)).xy).xyz - 1.0) * scale_xy;
+    tnormal_x.x *= -triplanar_sign.x;
+    tnormal_y.x *= triplanar_sign.y;
+    tnormal_z.x *= triplanar_sign.z;
+    tnormal_y.y *= -1.0;
+    vec3 n = abs(normal);
+    tnormal_x = vec3(tnormal_x.xy + normal.zy, n.x * tnormal_x.z);
+    tnormal_y = vec3(tnormal_y.xy + normal.xz, n.y * tnormal_y.z);
+    tnormal_z = vec3(tnormal_z.xy + normal.xy, n.z * tnormal_z.z);
+    tnormal_x.z *= triplanar_sign.x;
+    tnormal_y.z *= triplanar_sign.y;
+    tnormal_z.z *= triplanar_sign.z;
+    return normalize(tnormal_x.zyx * triplanar_weights.x + tnormal_y.xzy * triplanar_weights.y + tnormal_z.xyz * triplanar_weights.z);
+}
+#elif defined(USE_UVS)
+vec2 texture_uvs;
+
+vec4 sample_texture(sampler2D tex, mat3 transform)
+{
+    return texture(tex, (transform * vec3(texture_uvs, 1.0)).xy);
+}
+
+#if defined(USE_NORMAL_TEXTURE) || defined(USE_CLEARCOAT_NORMAL_TEXTURE)
+vec3 sample_normal_texture(sampler2D tex, mat3 transform, vec3 normal, float scale)
+{
+    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
+    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+    return tbn * ((2.0 * sample_texture(tex, transform).xyz - 1.0) * vec3(scale, scale, 1.0));
+}
+#endif
+#endif
+
+#ifdef USE_PARALLAX
+uniform sampler2D heightTexture;
+uniform mat3 heightTexTransform;
+uniform float heightScale;
+uniform int parallaxSteps;
+uniform int parallaxShadow;
+
+mat3 parallax_tbn;
+vec2 parallax_uv;
+float parallax_depth;
+vec2 parallax_dx;
+vec2 parallax_dy;
+
+float parallax_sample_depth(vec2 uv)
+{
+    return 1.0 - textureGrad(heightTexture, (heightTexTransform * vec3(uv, 1.0)).xy, parallax_dx, parallax_dy).r;
+}
+
+// Marches along the view direction given in tangent space until the height field is hit and returns the uv coordinates of the intersection
+vec2 parallax_occlusion_mapping(vec2 uv, vec3 view_direction)
+{
+    float layer_depth = 1.0 / float(parallaxSteps);
+    vec2 delta = view_direction.xy / max(view_direction.z, 0.001) * heightScale * layer_depth;
+    float current_layer_depth = 0.0;
+    float current_depth = parallax_sample_depth(uv);
+    for (int i = 0; i < parallaxSteps && current_layer_depth < current_depth; i++) {
+        uv -= delta;
+        current_depth = parallax_sample_depth(uv);
+        current_layer_depth += layer_depth;
+    }
+
+    // Interpolate between the last two layers
+    float after = current_depth - current_layer_depth;
+    float before = parallax_sample_depth(uv + delta) - current_layer_depth + layer_depth;
+    float weight = abs(after - before) > 0.00001 ? after / (after - before) : 0.0;
+    parallax_depth = current_layer_depth - weight * layer_depth;
+    return mix(uv, uv + delta, weight);
+}
+
+// Marches from the intersection with the height field towards the light and returns zero if the light is blocked by the height field
+float parallax_shadow(vec3 L)
+{
+    vec3 light_direction = transpose(parallax_tbn) * L;
+    if (parallaxShadow == 0 || light_direction.z <= 0.0 || parallax_depth <= 0.0) {
+        return 1.0;
+    }
+    float layer_depth = parallax_depth / float(parallaxSteps);
+    vec2 delta = light_direction.xy / light_direction.z * heightScale * layer_depth;
+    vec2 uv = parallax_uv;
+    float current_layer_depth = parallax_depth;
+    float occlusion = 0.0;
+    for (int i = 1; i < parallaxSteps; i++) {
+        uv += delta;
+        current_layer_depth -= layer_depth;
+        occlusion = max(occlusion, (current_layer_depth - parallax_sample_depth(uv)) * (1.0 - float(i) / float(parallaxSteps)));
+    }
+    return 1.0 - saturate(occlusion * float(parallaxSteps));
+}
+#endif
+
 void main()
 {
+#ifdef USE_TRIPLANAR
+    init_triplanar();
+#endif
+#ifdef USE_UVS
+    texture_uvs = uvs;
+#endif
+#ifdef USE_PARALLAX
+    parallax_tbn = mat3(normalize(gl_FrontFacing ? tang : -tang), normalize(gl_FrontFacing ? bitang : -bitang), normalize(gl_FrontFacing ? nor : -nor));
+    vec2 height_uvs = (heightTexTransform * vec3(uvs, 1.0)).xy;
+    parallax_dx = dFdx(height_uvs);
+    parallax_dy = dFdy(height_uvs);
+    texture_uvs = parallax_occlusion_mapping(uvs, normalize(transpose(parallax_tbn) * (cameraPosition - pos)));
+    parallax_uv = texture_uvs;
+#endif
+
     vec4 surface_color = albedo * col;
 #ifdef USE_ALBEDO_TEXTURE
-    vec4 c = texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
-    #ifdef ALPHACUT
-        if (c.a < acut) discard;
-    #endif
-    surface_color *= c;
+    surface_color *= sample_texture(albedoTexture, albedoTexTransform);
+#endif
+
+#ifdef ALPHACUT
+#ifdef ALPHA_TO_COVERAGE
+    // Sharpen the alpha value around the cutout so that the edge is anti-aliased by the coverage
+    surface_color.a = (surface_color.a - alphaCutout) / max(fwidth(surface_color.a), 0.0001) + 0.5;
+    if (surface_color.a <= 0.0) discard;
+#else
+    if (surface_color.a < alphaCutout) discard;
+    surface_color.a = 1.0;
+#endif
 #endif
 
     float metallic_factor = metallic;
     float roughness_factor = roughness;
 #ifdef USE_METALLIC_ROUGHNESS_TEXTURE
-    vec2 t = texture(metallicRoughnessTexture, (metallicRoughnessTexTransform * vec3(uvs, 1.0)).xy).gb;
+    vec2 t = sample_texture(metallicRoughnessTexture, metallicRoughnessTexTransform).gb;
     roughness_factor *= t.x;
     metallic_factor *= t.y;
 #endif
 
     float occlusion = 1.0;
 #ifdef USE_OCCLUSION_TEXTURE
-    occlusion = mix(1.0, texture(occlusionTexture, (occlusionTexTransform * vec3(uvs, 1.0)).xy).r, occlusionStrength);
+    occlusion = mix(1.0, sample_texture(occlusionTexture, occlusionTexTransform).r, occlusionStrength);
 #endif
 
     vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+    clearcoat_normal = normal;
+#ifdef USE_CLEARCOAT_NORMAL_TEXTURE
+    clearcoat_normal = normalize(sample_normal_texture(clearcoatNormalTexture, clearcoatNormalTexTransform, normal, 1.0));
+#endif
 #ifdef USE_NORMAL_TEXTURE
-    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
-    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
-    mat3 tbn = mat3(tangent, bitangent, normal);
-    normal = tbn * ((2.0 * texture(normalTexture, (normalTexTransform * vec3(uvs, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0));
+    normal = sample_normal_texture(normalTexture, normalTexTransform, normal, normalScale);
 #endif
 
     vec3 total_emissive = emissive.rgb;
 #ifdef USE_EMISSIVE_TEXTURE
-    total_emissive *= texture(emissiveTexture, (emissiveTexTransform * vec3(uvs, 1.0)).xy).rgb;
+    total_emissive *= sample_texture(emissiveTexture, emissiveTexTransform).rgb;
 #endif
 
+#ifdef USE_TRANSMISSION
+    float transmission_factor = transmission * (1.0 - metallic_factor);
+#ifdef USE_TRANSMISSION_TEXTURE
+    transmission_factor *= sample_texture(transmissionTexture, transmissionTexTransform).r;
+#endif
+    vec3 view_direction = normalize(pos - cameraPosition);
+    vec3 refraction_direction = refract(view_direction, normalize(normal), 1.0 / indexOfRefraction);
+    vec2 scene_uv = screen_uv(pos + refraction_direction * thickness);
+    // Objects in front of the surface are not refracted
+    if ((texture(sceneDepthMap, scene_uv).r - gl_FragCoord.z) * (2.0 * farDepth - 1.0) < 0.0) {
+        scene_uv = screen_uv(pos);
+    }
+    float lod = roughness_factor * log2(float(max(textureSize(sceneColorMap, 0).x, textureSize(sceneColorMap, 0).y)));
+    vec4 scene_color = textureLod(sceneColorMap, scene_uv, lod);
+    vec3 attenuation = pow(max(attenuationColor, vec3(0.0001)), vec3(thickness / attenuationDistance));
+    vec3 transmitted = transmission_factor * surface_color.rgb * attenuation * scene_color.rgb;
+    surface_color.rgb *= 1.0 - transmission_factor;
+#endif
+
+    specular_factor = specular;
+    specular_color = specularColor;
+    clearcoat_factor = clearcoat;
+    clearcoat_roughness = clearcoatRoughness;
+    sheen_color = sheenColor;
+    sheen_roughness = sheenRoughness;
+
     outColor.rgb = total_emissive + calculate_lighting(cameraPosition, surface_color.rgb, pos, normal, metallic_factor, roughness_factor, occlusion);
     outColor.rgb = tone_mapping(outColor.rgb);
     outColor.rgb = color_mapping(outColor.rgb);
+#ifdef USE_TRANSMISSION
+    outColor.rgb += transmitted;
+    outColor.a = transmission_factor * (1.0 - scene_color.a);
+#else
     outColor.a = surface_color.a;
-}
\ No newline at end of file
+#endif
+
+#ifdef WEIGHTED_BLENDED
+    outColor = weighted_blended_output(outColor);
+#endif
+}
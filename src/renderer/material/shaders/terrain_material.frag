@@ -0,0 +1,97 @@
+uniform vec3 cameraPosition;
+uniform float metallic;
+uniform float roughness;
+
+uniform sampler2DArray albedoTextures;
+#ifdef USE_NORMAL_TEXTURES
+uniform sampler2DArray normalTextures;
+uniform float normalScale;
+#endif
+#ifdef USE_ORM_TEXTURES
+uniform sampler2DArray ormTextures;
+#endif
+#ifdef USE_SPLAT_TEXTURES
+uniform sampler2DArray splatTextures;
+uniform vec4 splatArea;
+#endif
+
+uniform float layerTiling[LAYER_COUNT];
+uniform vec3 layerHeight[LAYER_COUNT];
+uniform vec3 layerSlope[LAYER_COUNT];
+
+in vec3 pos;
+in vec3 nor;
+
+layout (location = 0) out vec4 outColor;
+
+// Returns one inside the range given by x and y, zero outside and a linear transition of width z at both ends
+float range_weight(float value, vec3 range)
+{
+    float transition = max(range.z, 0.0001);
+    return saturate((value - range.x) / transition + 1.0) * saturate((range.y - value) / transition + 1.0);
+}
+
+void main()
+{
+    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+    float slope = acos(clamp(normal.y, -1.0, 1.0));
+
+    vec4 splat0 = vec4(1.0);
+    vec4 splat1 = vec4(1.0);
+#ifdef USE_SPLAT_TEXTURES
+    vec2 splat_uv = (pos.xz - splatArea.xy) / splatArea.zw;
+    splat0 = texture(splatTextures, vec3(splat_uv, 0.0));
+#if LAYER_COUNT > 4
+    splat1 = texture(splatTextures, vec3(splat_uv, 1.0));
+#endif
+#endif
+
+    // Compute the weight of each layer
+    float weights[LAYER_COUNT];
+    float total_weight = 0.0;
+    for (int i = 0; i < LAYER_COUNT; i++) {
+        float weight = i < 4 ? splat0[i] : splat1[i - 4];
+        weight *= range_weight(pos.y, layerHeight[i]) * range_weight(slope, layerSlope[i]);
+        weights[i] = weight;
+        total_weight += weight;
+    }
+    if (total_weight < 0.0001) {
+        weights[0] = 1.0;
+        total_weight = 1.0;
+    }
+
+    // Blend the layers
+    vec2 dx = dFdx(pos.xz);
+    vec2 dy = dFdy(pos.xz);
+    vec3 surface_color = vec3(0.0);
+    vec3 orm = vec3(0.0);
+    vec3 tangent_normal = vec3(0.0);
+    for (int i = 0; i < LAYER_COUNT; i++) {
+        float weight = weights[i] / total_weight;
+        if (weight > 0.0) {
+            float tiling = layerTiling[i];
+            vec3 uv = vec3(pos.xz * tiling, float(i));
+            surface_color += weight * textureGrad(albedoTextures, uv, dx * tiling, dy * tiling).rgb;
+#ifdef USE_ORM_TEXTURES
+            orm += weight * textureGrad(ormTextures, uv, dx * tiling, dy * tiling).rgb;
+#else
+            orm += weight * vec3(1.0);
+#endif
+#ifdef USE_NORMAL_TEXTURES
+            tangent_normal += weight * (2.0 * textureGrad(normalTextures, uv, dx * tiling, dy * tiling).xyz - 1.0);
+#endif
+        }
+    }
+
+#ifdef USE_NORMAL_TEXTURES
+    // The textures are mapped along the x and z axes
+    vec3 tangent = normalize(vec3(1.0, 0.0, 0.0) - normal * normal.x);
+    vec3 bitangent = cross(tangent, normal);
+    normal = normalize(mat3(tangent, bitangent, normal) * (tangent_normal * vec3(normalScale, normalScale, 1.0)));
+#endif
+
+    outColor.rgb = calculate_lighting(cameraPosition, surface_color, pos, normal, metallic * orm.b, roughness * orm.g, orm.r);
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    outColor.a = 1.0;
+}
@@ -0,0 +1,78 @@
+uniform vec3 cameraPosition;
+
+uniform vec4 albedo;
+#ifdef USE_ALBEDO_TEXTURE
+uniform sampler2D albedoTexture;
+uniform mat3 albedoTexTransform;
+#endif
+
+#ifdef USE_NORMAL_TEXTURE
+uniform sampler2D normalTexture;
+uniform mat3 normalTexTransform;
+uniform float normalScale;
+#endif
+
+#ifdef USE_RAMP_TEXTURE
+uniform sampler2D rampTexture;
+#else
+uniform float diffuseBands;
+#endif
+
+uniform vec3 specularColor;
+uniform float specularSize;
+uniform vec3 rimColor;
+uniform float rimSize;
+
+in vec3 pos;
+in vec3 nor;
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+vec3 calculate_toon_light(vec3 light_color, vec3 L, vec3 surface_color, vec3 V, vec3 N)
+{
+    float NdL = dot(N, L);
+    float NdV = max(dot(N, V), 0.0);
+
+    // Diffuse
+#ifdef USE_RAMP_TEXTURE
+    vec3 diffuse = texture(rampTexture, vec2(0.5 * NdL + 0.5, 0.5)).rgb;
+#else
+    vec3 diffuse = vec3(ceil(max(NdL, 0.0) * diffuseBands) / diffuseBands);
+#endif
+
+    // Hard specular highlight
+    float NdH = dot(N, normalize(L + V));
+    float specular = NdL > 0.0 && specularSize > 0.0 ? smoothstep(0.0, 0.01, NdH - 1.0 + specularSize) : 0.0;
+
+    // Rim light on the lit side of the silhouette
+    float rim = (1.0 - NdV) * pow(max(NdL, 0.0), 0.1);
+    rim = rimSize > 0.0 ? smoothstep(0.0, 0.01, rim - 1.0 + rimSize) : 0.0;
+
+    return (surface_color * diffuse + specularColor * specular + rimColor * rim) * light_color;
+}
+
+void main()
+{
+    vec4 surface_color = albedo * col;
+#ifdef USE_ALBEDO_TEXTURE
+    surface_color *= texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
+#endif
+
+    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+#ifdef USE_NORMAL_TEXTURE
+    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
+    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+    normal = normalize(tbn * ((2.0 * texture(normalTexture, (normalTexTransform * vec3(uvs, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0)));
+#endif
+
+    outColor.rgb = calculate_lighting(cameraPosition, surface_color.rgb, pos, normal, 0.0, 1.0, 1.0);
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    outColor.a = surface_color.a;
+
+#ifdef WEIGHTED_BLENDED
+    outColor = weighted_blended_output(outColor);
+#endif
+}
@@ -0,0 +1,15 @@
+uniform mat4 currentViewProjection;
+uniform mat4 previousViewProjection;
+
+in vec3 pos;
+in vec3 prev_pos;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec4 current = currentViewProjection * vec4(pos, 1.0);
+    vec4 previous = previousViewProjection * vec4(prev_pos, 1.0);
+    vec2 velocity = 0.5 * (current.xy / current.w - previous.xy / previous.w);
+    outColor = vec4(velocity, 0.0, 1.0);
+}
@@ -0,0 +1,235 @@
+use crate::core::*;
+use crate::renderer::*;
+use std::sync::Arc;
+
+///
+/// The parameters for one layer of a [TerrainMaterial].
+///
+#[derive(Clone, Copy, Debug)]
+pub struct TerrainLayer {
+    /// The number of times the textures of this layer are repeated per world space unit.
+    pub tiling: f32,
+    /// The minimum and maximum height, ie. the y-coordinate in world space, where this layer is applied.
+    pub height_range: (f32, f32),
+    /// The width of the transition at the ends of the [Self::height_range] in world space units.
+    pub height_transition: f32,
+    /// The minimum and maximum slope where this layer is applied, where the slope is the angle between the surface normal and the y-axis.
+    pub slope_range: (Radians, Radians),
+    /// The width of the transition at the ends of the [Self::slope_range].
+    pub slope_transition: Radians,
+}
+
+impl Default for TerrainLayer {
+    fn default() -> Self {
+        Self {
+            tiling: 1.0,
+            height_range: (f32::MIN, f32::MAX),
+            height_transition: 0.0,
+            slope_range: (radians(0.0), degrees(90.0).into()),
+            slope_transition: radians(0.0),
+        }
+    }
+}
+
+///
+/// A physically-based material for a [Terrain] which blends up to eight layers, for example grass, rock and snow.
+/// Each layer consists of an albedo texture and optionally a normal and an occlusion, roughness and metallic texture,
+/// which are stored as the layers of a [Texture2DArray] for each type of texture.
+/// The textures are mapped onto the terrain along the x and z axes and repeated according to [TerrainLayer::tiling],
+/// so the geometry does not need uv coordinates or tangents.
+///
+/// The weight of each layer is the product of the weight sampled from the [Self::splat_textures] and the weight given by the height and slope rules of the layer (see [TerrainLayer]).
+/// The final color is the weighted average of the layers.
+/// This material is affected by lights.
+///
+#[derive(Clone)]
+pub struct TerrainMaterial {
+    /// The albedo textures with one layer in the texture array for each terrain layer.
+    /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
+    pub albedo_textures: Arc<Texture2DArray>,
+    /// Tangent space normal maps with one layer in the texture array for each terrain layer.
+    pub normal_textures: Option<Arc<Texture2DArray>>,
+    /// A scalar multiplier applied to each normal vector of the [Self::normal_textures].
+    pub normal_scale: f32,
+    /// Textures containing the occlusion, roughness and metallic parameters with one layer in the texture array for each terrain layer.
+    /// The occlusion values are sampled from the red channel, the roughness from the green channel and the metallic from the blue channel.
+    pub orm_textures: Option<Arc<Texture2DArray>>,
+    /// A value in the range `[0..1]` specifying how metallic the surface is.
+    pub metallic: f32,
+    /// A value in the range `[0..1]` specifying how rough the surface is.
+    pub roughness: f32,
+    /// RGBA weight maps, also called splat maps, where the red, green, blue and alpha channels of the first layer in the texture array specify the weights of the first four terrain layers
+    /// and the second layer in the texture array specify the weights of the next four terrain layers.
+    /// The splat maps cover the [Self::splat_area] and are therefore independent of the tiling of the terrain layers.
+    /// If `None`, the weights are given by the height and slope rules only.
+    pub splat_textures: Option<Arc<Texture2DArray>>,
+    /// The area in the xz-plane covered by the [Self::splat_textures] given by the minimum x and z coordinates and the size along the x and z axes.
+    pub splat_area: (Vec2, Vec2),
+    layers: Vec<TerrainLayer>,
+    /// The lighting model used when rendering this material
+    pub lighting_model: LightingModel,
+    /// Render states.
+    pub render_states: RenderStates,
+}
+
+impl TerrainMaterial {
+    ///
+    /// Constructs a new terrain material with the given albedo textures, one layer in the texture array for each terrain layer, and the given terrain layer parameters.
+    ///
+    /// # Panic
+    /// Will panic if there are no layers or more than eight layers.
+    ///
+    pub fn new(albedo_textures: Arc<Texture2DArray>, layers: Vec<TerrainLayer>) -> Self {
+        check_layer_count(layers.len());
+        Self {
+            albedo_textures,
+            normal_textures: None,
+            normal_scale: 1.0,
+            orm_textures: None,
+            metallic: 0.0,
+            roughness: 1.0,
+            splat_textures: None,
+            splat_area: (vec2(0.0, 0.0), vec2(1.0, 1.0)),
+            layers,
+            lighting_model: LightingModel::Blinn,
+            render_states: RenderStates::default(),
+        }
+    }
+
+    ///
+    /// Returns the parameters for each terrain layer.
+    ///
+    pub fn layers(&self) -> &[TerrainLayer] {
+        &self.layers
+    }
+
+    ///
+    /// Returns the parameters for each terrain layer, which can be changed but not added or removed, see [TerrainMaterial::set_layers].
+    ///
+    pub fn layers_mut(&mut self) -> &mut [TerrainLayer] {
+        &mut self.layers
+    }
+
+    ///
+    /// Sets the parameters for each terrain layer, one for each layer in the texture arrays.
+    ///
+    /// # Panic
+    /// Will panic if there are no layers or more than eight layers.
+    ///
+    pub fn set_layers(&mut self, layers: Vec<TerrainLayer>) {
+        check_layer_count(layers.len());
+        self.layers = layers;
+    }
+}
+
+fn check_layer_count(layer_count: usize) {
+    if layer_count == 0 || layer_count > 8 {
+        panic!(
+            "A terrain material must have between one and eight layers, but it has {} layers",
+            layer_count
+        );
+    }
+}
+
+impl Material for TerrainMaterial {
+    fn id(&self) -> u16 {
+        let mut id = 0b1u16 << 15 | 0b1u16 << 10 | (self.layers.len() as u16 - 1) << 3;
+        if self.normal_textures.is_some() {
+            id |= 0b1u16;
+        }
+        if self.orm_textures.is_some() {
+            id |= 0b1u16 << 1;
+        }
+        if self.splat_textures.is_some() {
+            id |= 0b1u16 << 2;
+        }
+        id
+    }
+
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        let mut output = lights_shader_source(lights, self.lighting_model);
+        output.push_str(&format!("#define LAYER_COUNT {}\n", self.layers.len()));
+        if self.normal_textures.is_some() {
+            output.push_str("#define USE_NORMAL_TEXTURES;\n");
+        }
+        if self.orm_textures.is_some() {
+            output.push_str("#define USE_ORM_TEXTURES;\n");
+        }
+        if self.splat_textures.is_some() {
+            output.push_str("#define USE_SPLAT_TEXTURES;\n");
+        }
+        output.push_str(ToneMapping::fragment_shader_source());
+        output.push_str(ColorMapping::fragment_shader_source());
+        output.push_str(include_str!("shaders/terrain_material.frag"));
+        output
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            position: true,
+            normal: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.color_mapping.use_uniforms(program);
+        if !lights.is_empty() {
+            program.use_uniform_if_required("cameraPosition", camera.position());
+            for (i, light) in lights.iter().enumerate() {
+                light.use_uniforms(program, i as u32);
+            }
+            program.use_uniform_if_required("metallic", self.metallic);
+            program.use_uniform_if_required("roughness", self.roughness);
+            program.use_uniform_array(
+                "layerTiling",
+                &self.layers.iter().map(|l| l.tiling).collect::<Vec<_>>(),
+            );
+            program.use_uniform_array(
+                "layerHeight",
+                &self
+                    .layers
+                    .iter()
+                    .map(|l| vec3(l.height_range.0, l.height_range.1, l.height_transition))
+                    .collect::<Vec<_>>(),
+            );
+            program.use_uniform_array(
+                "layerSlope",
+                &self
+                    .layers
+                    .iter()
+                    .map(|l| vec3(l.slope_range.0 .0, l.slope_range.1 .0, l.slope_transition.0))
+                    .collect::<Vec<_>>(),
+            );
+            program.use_texture_array("albedoTextures", &self.albedo_textures);
+            if let Some(ref textures) = self.normal_textures {
+                program.use_uniform("normalScale", self.normal_scale);
+                program.use_texture_array("normalTextures", textures);
+            }
+            if let Some(ref textures) = self.orm_textures {
+                program.use_texture_array("ormTextures", textures);
+            }
+            if let Some(ref textures) = self.splat_textures {
+                program.use_uniform(
+                    "splatArea",
+                    vec4(
+                        self.splat_area.0.x,
+                        self.splat_area.0.y,
+                        self.splat_area.1.x,
+                        self.splat_area.1.y,
+                    ),
+                );
+                program.use_texture_array("splatTextures", textures);
+            }
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
@@ -0,0 +1,213 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// A non-photorealistic material that renders a [Geometry] with a cartoon look, also called cel shading.
+/// The diffuse light is quantized into a number of bands or looked up in a ramp texture and the specular highlight and rim light have hard edges.
+/// This material is affected by lights, however, the light from an [AmbientLight] is not quantized.
+/// To draw silhouettes around the objects, apply a [ToonOutlineEffect] afterwards.
+///
+#[derive(Clone)]
+pub struct ToonMaterial {
+    /// Name.
+    pub name: String,
+    /// Albedo base color, also called diffuse color.
+    pub albedo: Srgba,
+    /// Texture with albedo base colors, also called diffuse color.
+    /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
+    pub albedo_texture: Option<Texture2DRef>,
+    /// A scalar multiplier applied to each normal vector of the [Self::normal_texture].
+    pub normal_scale: f32,
+    /// A tangent space normal map, also known as bump map.
+    pub normal_texture: Option<Texture2DRef>,
+    /// The number of bands the diffuse light is quantized into. Only used if no [Self::ramp_texture] is specified.
+    pub diffuse_bands: u32,
+    /// A texture which maps the amount of diffuse light to a color which is multiplied with the albedo color.
+    /// The texture is sampled along the horizontal axis with the left edge corresponding to surfaces facing away from the light and the right edge to surfaces facing the light.
+    pub ramp_texture: Option<Texture2DRef>,
+    /// The color of the specular highlight.
+    pub specular_color: Srgba,
+    /// A value in the range `[0..1]` specifying the size of the specular highlight. The highlight is disabled if the size is zero.
+    pub specular_size: f32,
+    /// The color of the rim light, ie. the light at the silhouette of the object on the side facing the light.
+    pub rim_color: Srgba,
+    /// A value in the range `[0..1]` specifying the size of the rim light. The rim light is disabled if the size is zero.
+    pub rim_size: f32,
+    /// Render states.
+    pub render_states: RenderStates,
+    /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
+    pub is_transparent: bool,
+}
+
+impl ToonMaterial {
+    ///
+    /// Constructs a new toon material from a [CpuMaterial] using the albedo and normal parameters.
+    /// Tries to infer whether this material is transparent or opaque from the alpha value of the albedo color and the alpha values in the albedo texture.
+    ///
+    pub fn new(context: &Context, cpu_material: &CpuMaterial) -> Self {
+        let albedo_texture =
+            cpu_material
+                .albedo_texture
+                .as_ref()
+                .map(|cpu_texture| match &cpu_texture.data {
+                    TextureData::RgbU8(_) | TextureData::RgbaU8(_) => {
+                        let mut cpu_texture = cpu_texture.clone();
+                        cpu_texture.data.to_linear_srgb();
+                        Texture2DRef::from_cpu_texture(context, &cpu_texture)
+                    }
+                    _ => Texture2DRef::from_cpu_texture(context, cpu_texture),
+                });
+        let normal_texture = cpu_material
+            .normal_texture
+            .as_ref()
+            .map(|cpu_texture| Texture2DRef::from_cpu_texture(context, cpu_texture));
+        let is_transparent = super::is_alpha_blended(cpu_material);
+        Self {
+            name: cpu_material.name.clone(),
+            albedo: cpu_material.albedo,
+            albedo_texture,
+            normal_scale: cpu_material.normal_scale,
+            normal_texture,
+            is_transparent,
+            render_states: if is_transparent {
+                RenderStates {
+                    write_mask: WriteMask::COLOR,
+                    blend: Blend::TRANSPARENCY,
+                    ..Default::default()
+                }
+            } else {
+                RenderStates::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+impl FromCpuMaterial for ToonMaterial {
+    fn from_cpu_material(context: &Context, cpu_material: &CpuMaterial) -> Self {
+        Self::new(context, cpu_material)
+    }
+}
+
+impl Material for ToonMaterial {
+    fn id(&self) -> u16 {
+        let mut id = 0b1u16 << 15 | 0b1u16 << 8;
+        if self.albedo_texture.is_some() {
+            id |= 0b1u16;
+        }
+        if self.normal_texture.is_some() {
+            id |= 0b1u16 << 1;
+        }
+        if self.ramp_texture.is_some() {
+            id |= 0b1u16 << 2;
+        }
+        id
+    }
+
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        let mut output = "#define TOON\n".to_string();
+        output.push_str(&lights_shader_source(lights, LightingModel::Blinn));
+        if self.albedo_texture.is_some() || self.normal_texture.is_some() {
+            output.push_str("in vec2 uvs;\n");
+            if self.albedo_texture.is_some() {
+                output.push_str("#define USE_ALBEDO_TEXTURE;\n");
+            }
+            if self.normal_texture.is_some() {
+                output.push_str("#define USE_NORMAL_TEXTURE;\nin vec3 tang;\nin vec3 bitang;\n");
+            }
+        }
+        if self.ramp_texture.is_some() {
+            output.push_str("#define USE_RAMP_TEXTURE;\n");
+        }
+        output.push_str(ToneMapping::fragment_shader_source());
+        output.push_str(ColorMapping::fragment_shader_source());
+        output.push_str(include_str!("shaders/toon_material.frag"));
+        output
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            position: true,
+            normal: true,
+            color: true,
+            uv: self.albedo_texture.is_some() || self.normal_texture.is_some(),
+            tangents: self.normal_texture.is_some(),
+            velocity: false,
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.color_mapping.use_uniforms(program);
+        if !lights.is_empty() {
+            program.use_uniform_if_required("cameraPosition", camera.position());
+            for (i, light) in lights.iter().enumerate() {
+                light.use_uniforms(program, i as u32);
+            }
+            if let Some(ref texture) = self.ramp_texture {
+                if program.requires_uniform("rampTexture") {
+                    program.use_texture("rampTexture", texture);
+                }
+            } else {
+                program.use_uniform_if_required("diffuseBands", self.diffuse_bands.max(1) as f32);
+            }
+            program.use_uniform_if_required(
+                "specularColor",
+                self.specular_color.to_linear_srgb().truncate(),
+            );
+            program.use_uniform_if_required("specularSize", self.specular_size);
+            program.use_uniform_if_required("rimColor", self.rim_color.to_linear_srgb().truncate());
+            program.use_uniform_if_required("rimSize", self.rim_size);
+            if program.requires_uniform("normalTexture") {
+                if let Some(ref texture) = self.normal_texture {
+                    program.use_uniform("normalTexTransform", texture.transformation);
+                    program.use_uniform("normalScale", self.normal_scale);
+                    program.use_texture("normalTexture", texture);
+                }
+            }
+        }
+        program.use_uniform("albedo", self.albedo.to_linear_srgb());
+        if program.requires_uniform("albedoTexture") {
+            if let Some(ref texture) = self.albedo_texture {
+                program.use_uniform("albedoTexTransform", texture.transformation);
+                program.use_texture("albedoTexture", texture);
+            }
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        if self.is_transparent {
+            MaterialType::Transparent
+        } else {
+            MaterialType::Opaque
+        }
+    }
+
+    fn supports_weighted_blended(&self) -> bool {
+        true
+    }
+}
+
+impl Default for ToonMaterial {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            albedo: Srgba::WHITE,
+            albedo_texture: None,
+            normal_scale: 1.0,
+            normal_texture: None,
+            diffuse_bands: 3,
+            ramp_texture: None,
+            specular_color: Srgba::WHITE,
+            specular_size: 0.05,
+            rim_color: Srgba::WHITE,
+            rim_size: 0.3,
+            render_states: RenderStates::default(),
+            is_transparent: false,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Render the object with colors that reflect its motion on the screen since the previous frame, which is used by the [TemporalAntiAliasingEffect] and the [MotionBlurEffect].
+/// The red and green channels contain the difference between the current and previous position in uv coordinates, ie. in the range `[0..1]` across the screen.
+/// The motion is calculated from the previous transformation of the geometry (see [FragmentAttributes::velocity]) and the previous view-projection of the camera (see [Camera::previous_view_projection])
+/// and does not include the [Camera::jitter].
+/// Render into a texture with a floating point format, for example `[f16; 4]`, cleared to zero.
+///
+#[derive(Default, Clone)]
+pub struct VelocityMaterial {
+    /// Render states.
+    pub render_states: RenderStates,
+}
+
+impl FromCpuMaterial for VelocityMaterial {
+    fn from_cpu_material(_context: &Context, _cpu_material: &CpuMaterial) -> Self {
+        Self::default()
+    }
+}
+
+impl Material for VelocityMaterial {
+    fn id(&self) -> u16 {
+        0b1u16 << 15 | 0b1000u16
+    }
+
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        include_str!("shaders/velocity_material.frag").to_string()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            position: true,
+            velocity: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, _lights: &[&dyn Light]) {
+        program.use_uniform(
+            "currentViewProjection",
+            camera.projection_without_jitter() * camera.view(),
+        );
+        program.use_uniform("previousViewProjection", camera.previous_view_projection());
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
@@ -15,6 +15,17 @@ macro_rules! impl_object_body {
         fn material_type(&self) -> MaterialType {
             self.$inner().material_type()
         }
+
+        fn state_key(&self) -> Option<StateKey> {
+            self.$inner().state_key()
+        }
+
+        fn with_geometry_and_material(
+            &self,
+            callback: &mut dyn FnMut(&dyn Geometry, &dyn Material),
+        ) -> bool {
+            self.$inner().with_geometry_and_material(callback)
+        }
     };
 }
 
@@ -72,6 +83,27 @@ pub trait Object: Geometry {
     /// Returns the type of material applied to this object.
     ///
     fn material_type(&self) -> MaterialType;
+
+    ///
+    /// Returns a key identifying the shader program used when rendering this object.
+    /// This is used to group objects that require the same state when sorting them with [RenderOrder::State].
+    /// Returns `None` if the object does not render with a single known program, in which case it is only sorted by distance.
+    ///
+    fn state_key(&self) -> Option<StateKey> {
+        None
+    }
+
+    ///
+    /// Calls the given closure with the geometry and material of this object if the object is rendered with [Object::render] by a single call to [Geometry::render_with_material].
+    /// This makes it possible to render the object with a modified version of its material, for example in a depth pre-pass (see [RenderSettings::depth_pre_pass]).
+    /// Returns `false` without calling the closure otherwise.
+    ///
+    fn with_geometry_and_material(
+        &self,
+        _callback: &mut dyn FnMut(&dyn Geometry, &dyn Material),
+    ) -> bool {
+        false
+    }
 }
 
 use std::ops::Deref;
@@ -96,7 +128,24 @@ impl<T: Object> Object for std::sync::Arc<T> {
 }
 
 impl<T: Object> Object for std::cell::RefCell<T> {
-    impl_object_body!(borrow);
+    fn render(&self, camera: &Camera, lights: &[&dyn Light]) {
+        self.borrow().render(camera, lights)
+    }
+
+    fn material_type(&self) -> MaterialType {
+        self.borrow().material_type()
+    }
+
+    fn state_key(&self) -> Option<StateKey> {
+        self.borrow().state_key()
+    }
+
+    fn with_geometry_and_material(
+        &self,
+        callback: &mut dyn FnMut(&dyn Geometry, &dyn Material),
+    ) -> bool {
+        self.borrow().with_geometry_and_material(callback)
+    }
 }
 
 impl<T: Object> Object for std::sync::RwLock<T> {
@@ -107,4 +156,15 @@ impl<T: Object> Object for std::sync::RwLock<T> {
     fn material_type(&self) -> MaterialType {
         self.read().unwrap().material_type()
     }
+
+    fn state_key(&self) -> Option<StateKey> {
+        self.read().unwrap().state_key()
+    }
+
+    fn with_geometry_and_material(
+        &self,
+        callback: &mut dyn FnMut(&dyn Geometry, &dyn Material),
+    ) -> bool {
+        self.read().unwrap().with_geometry_and_material(callback)
+    }
 }
@@ -69,4 +69,19 @@ impl<G: Geometry, M: Material> Object for Gm<G, M> {
     fn material_type(&self) -> MaterialType {
         self.material.material_type()
     }
+
+    fn state_key(&self) -> Option<StateKey> {
+        Some(StateKey {
+            geometry_id: self.geometry.id(self.material.fragment_attributes()),
+            material_id: self.material.id(),
+        })
+    }
+
+    fn with_geometry_and_material(
+        &self,
+        callback: &mut dyn FnMut(&dyn Geometry, &dyn Material),
+    ) -> bool {
+        callback(&self.geometry, &self.material);
+        true
+    }
 }
@@ -1,6 +1,9 @@
 
+invariant gl_Position;
+
 uniform mat4 view;
 uniform mat4 projection;
+uniform float farNdcDepth;
 
 in vec3 position;
 
@@ -10,4 +13,5 @@ void main()
 {
     coords = position;
     gl_Position = (projection * mat4(mat3(view)) * vec4(position, 1.)).xyww;
+    gl_Position.z *= farNdcDepth;
 }
\ No newline at end of file
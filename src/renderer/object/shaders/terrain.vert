@@ -1,8 +1,11 @@
+invariant gl_Position;
+
 uniform mat4 viewProjectionMatrix;
 
 in vec3 position;
 
 out vec3 pos;
+out vec3 prev_pos;
 out vec2 uvs;
 out vec4 col;
 
@@ -19,6 +22,7 @@ void main()
 {
     vec4 worldPos = vec4(position, 1.);
     pos = worldPos.xyz;
+    prev_pos = pos;
     uvs = worldPos.xz;
     col = vec4(1.0);
 #ifdef USE_NORMALS
@@ -1,3 +1,5 @@
+invariant gl_Position;
+
 uniform vec3 offset;
 uniform mat4 viewProjection;
 uniform float time;
@@ -13,6 +15,7 @@ in vec3 position;
 out vec2 uvs;
 out vec3 nor;
 out vec3 pos;
+out vec3 prev_pos;
 out vec4 col;
 
 void main()
@@ -52,6 +55,7 @@ void main()
     }
     
     gl_Position = viewProjection * vec4(pos, 1.);
+    prev_pos = pos;
     uvs = pos.xz;
     col = vec4(1.0);
 }
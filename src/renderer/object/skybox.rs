@@ -158,6 +158,7 @@ impl Geometry for Skybox {
     ) {
         program.use_uniform("view", camera.view());
         program.use_uniform("projection", camera.projection());
+        program.use_uniform("farNdcDepth", camera.depth_mapping.far_ndc_depth());
         program.use_vertex_attribute("position", &self.vertex_buffer);
         program.draw_arrays(render_states, camera.viewport(), 36);
     }
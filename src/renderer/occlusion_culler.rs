@@ -0,0 +1,145 @@
+use crate::renderer::*;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+///
+/// Culls objects that are hidden behind other objects using hardware occlusion queries.
+/// Use it in a render call by setting [RenderSettings::occlusion_culler] and reuse the same culler for the same view every frame.
+/// The objects are identified by the [RenderSettings::object_ids] given to the render call,
+/// so each object must have the same id every frame for the results of the previous frames to be used.
+///
+/// After the objects are rendered, the bounding box of each object is tested against the depth buffer of the render target.
+/// To avoid waiting for the GPU, the results are used in the following render calls, which means that an object which becomes visible is rendered one frame late.
+/// Objects with an infinite bounding box and objects with a bounding box containing the camera are never culled.
+///
+/// **Note:** The render target needs a depth buffer, otherwise no objects are culled.
+///
+pub struct OcclusionCuller {
+    context: Context,
+    program: Program,
+    positions: VertexBuffer,
+    entries: RwLock<HashMap<u64, OcclusionEntry>>,
+}
+
+struct OcclusionEntry {
+    query: OcclusionQuery,
+    pending: bool,
+    visible: bool,
+    used: bool,
+}
+
+impl OcclusionCuller {
+    ///
+    /// Creates a new occlusion culler.
+    ///
+    pub fn new(context: &Context) -> Self {
+        let positions = CpuMesh::cube()
+            .positions
+            .to_f32()
+            .into_iter()
+            .map(|p| p * 0.5 + vec3(0.5, 0.5, 0.5))
+            .collect::<Vec<_>>();
+        Self {
+            context: context.clone(),
+            program: Program::from_source(
+                context,
+                "
+                    uniform mat4 viewProjection;
+                    uniform vec3 minimum;
+                    uniform vec3 extent;
+                    in vec3 position;
+                    void main()
+                    {
+                        gl_Position = viewProjection * vec4(minimum + extent * position, 1.0);
+                    }
+                ",
+                "
+                    layout (location = 0) out vec4 outColor;
+                    void main()
+                    {
+                        outColor = vec4(0.0);
+                    }
+                ",
+            )
+            .expect("Failed compiling shader"),
+            positions: VertexBuffer::new_with_data(context, &positions),
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    ///
+    /// Returns whether or not the object with the given id, see [RenderSettings::object_ids], was visible the last time its occlusion query finished.
+    /// Returns true if the visibility is unknown.
+    ///
+    pub fn is_visible(&self, id: u64) -> bool {
+        self.entries
+            .read()
+            .unwrap()
+            .get(&id)
+            .map(|entry| entry.visible)
+            .unwrap_or(true)
+    }
+
+    ///
+    /// Updates the visibility of the objects with the results of the occlusion queries that have finished since the last call.
+    ///
+    pub(in crate::renderer) fn update(&self) {
+        for entry in self.entries.write().unwrap().values_mut() {
+            if entry.pending {
+                if let Some(visible) = entry.query.result() {
+                    entry.visible = visible;
+                    entry.pending = false;
+                }
+            }
+        }
+    }
+
+    ///
+    /// Issues occlusion queries for the bounding boxes of the objects with the given ids against the depth buffer of the current render target.
+    /// Also removes the state of objects that are not given to this method.
+    /// Must be called in the callback given as input to a [RenderTarget] or [DepthTarget] write method.
+    ///
+    pub(in crate::renderer) fn query<'a>(
+        &self,
+        camera: &Camera,
+        aabbs: impl IntoIterator<Item = &'a (u64, AxisAlignedBoundingBox)>,
+    ) {
+        let mut entries = self.entries.write().unwrap();
+        entries.values_mut().for_each(|entry| entry.used = false);
+        self.program
+            .use_uniform("viewProjection", camera.projection() * camera.view());
+        for (id, aabb) in aabbs {
+            if aabb.is_empty() || aabb.is_infinite() {
+                continue;
+            }
+            let entry = entries.entry(*id).or_insert_with(|| OcclusionEntry {
+                query: OcclusionQuery::new(&self.context),
+                pending: false,
+                visible: true,
+                used: false,
+            });
+            entry.used = true;
+            if aabb.distance(camera.position()) <= camera.z_near() {
+                entry.visible = true;
+            } else if !entry.pending {
+                entry.query.begin();
+                self.program.use_uniform("minimum", aabb.min());
+                self.program.use_uniform("extent", aabb.size());
+                self.program
+                    .use_vertex_attribute("position", &self.positions);
+                self.program.draw_arrays(
+                    camera.depth_mapping.render_states(RenderStates {
+                        write_mask: WriteMask::NONE,
+                        depth_test: DepthTest::LessOrEqual,
+                        ..Default::default()
+                    }),
+                    camera.viewport(),
+                    self.positions.vertex_count(),
+                );
+                entry.query.end();
+                entry.pending = true;
+            }
+        }
+        entries.retain(|_, entry| entry.used);
+    }
+}
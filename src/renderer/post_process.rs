@@ -0,0 +1,192 @@
+use crate::renderer::*;
+
+///
+/// An ordered list of [Effect]s which are applied one after the other to a rendered scene before the result is written to a render target, usually the screen.
+///
+/// The stack owns the intermediate color and depth textures, which are resized to the viewport of the camera when needed.
+/// The scene is rendered into these textures using [PostProcessStack::render_scene] and the effects are applied and the result written to the render target using [PostProcessStack::apply].
+/// The scene and the effects are rendered without tone and color mapping, ie. in linear HDR color space,
+/// and the [ToneMapping] and [ColorMapping] specified in the [Camera] are applied exactly once when writing to the render target,
+/// followed by the [PostProcessStack::color_grading], if specified.
+///
+/// ```no_run
+/// # use three_d::*;
+/// # let context: Context = unimplemented!();
+/// # let camera: Camera = unimplemented!();
+/// # let screen: RenderTarget = unimplemented!();
+/// # let model: Gm<Mesh, PhysicalMaterial> = unimplemented!();
+/// # let light: DirectionalLight = unimplemented!();
+/// let mut stack = PostProcessStack::new(&context);
+/// stack.effects.push(Box::new(FxaaEffect::default()));
+/// stack.render_scene(&camera, |target, camera| {
+///     target
+///         .clear(camera.depth_mapping.clear_state(ClearState::default()))
+///         .render(camera, &model, &[&light]);
+/// });
+/// stack.apply(&screen, &camera, &[&light]);
+/// ```
+///
+pub struct PostProcessStack {
+    context: Context,
+    /// The effects which are applied in order.
+    /// Each effect is given the color and depth texture resulting from the previous effect, or the rendered scene for the first effect.
+    /// The color texture is a single texture, so the [TemporalAntiAliasingEffect] and [MotionBlurEffect] read the velocity from their own texture,
+    /// which must be rendered each frame using [TemporalAntiAliasingEffect::render_velocity] and [MotionBlurEffect::render_velocity].
+    /// To do that after adding the effect to the stack, add it wrapped in an [Rc](std::rc::Rc) and keep a clone.
+    pub effects: Vec<Box<dyn Effect>>,
+    /// The color grading which is applied after the tone mapping when writing to the render target.
+    pub color_grading: Option<ColorGradingEffect>,
+    color_textures: Vec<Texture2D>,
+    depth_textures: Vec<DepthTexture2D>,
+}
+
+impl PostProcessStack {
+    ///
+    /// Creates a new post processing stack without any effects.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            effects: Vec::new(),
+            color_grading: None,
+            color_textures: Vec::new(),
+            depth_textures: Vec::new(),
+        }
+    }
+
+    ///
+    /// Renders the scene into the intermediate textures.
+    /// The given closure is called with the render target to render into and the camera to use, which is a copy of the given camera
+    /// with the tone and color mapping disabled and the viewport adjusted to the intermediate textures.
+    /// Remember to clear the render target in the closure, using [DepthMapping::clear_state] to get the correct depth clear value for the camera.
+    ///
+    pub fn render_scene(&mut self, camera: &Camera, render: impl FnOnce(&RenderTarget, &Camera)) {
+        let viewport = camera.viewport();
+        if self
+            .color_textures
+            .first()
+            .map(|t| t.width() != viewport.width || t.height() != viewport.height)
+            .unwrap_or(true)
+        {
+            self.color_textures = (0..2)
+                .map(|_| {
+                    Texture2D::new_empty::<[f16; 4]>(
+                        &self.context,
+                        viewport.width,
+                        viewport.height,
+                        Interpolation::Linear,
+                        Interpolation::Linear,
+                        None,
+                        Wrapping::ClampToEdge,
+                        Wrapping::ClampToEdge,
+                    )
+                })
+                .collect();
+            self.depth_textures = (0..2)
+                .map(|_| {
+                    DepthTexture2D::new::<f32>(
+                        &self.context,
+                        viewport.width,
+                        viewport.height,
+                        Wrapping::ClampToEdge,
+                        Wrapping::ClampToEdge,
+                    )
+                })
+                .collect();
+        }
+        let camera = self.intermediate_camera(camera);
+        let target = RenderTarget::new(
+            self.color_textures[0].as_color_target(None),
+            self.depth_textures[0].as_depth_target(),
+        );
+        render(&target, &camera);
+    }
+
+    ///
+    /// Applies the effects in order to the scene rendered using [PostProcessStack::render_scene]
+    /// and writes the result with the tone and color mapping specified in the given camera and the [PostProcessStack::color_grading] applied to the given render target.
+    ///
+    /// # Panic
+    /// Will panic if [PostProcessStack::render_scene] has not been called before.
+    ///
+    pub fn apply(&mut self, target: &RenderTarget, camera: &Camera, lights: &[&dyn Light]) {
+        assert!(
+            !self.color_textures.is_empty(),
+            "The scene must be rendered using PostProcessStack::render_scene before applying the post processing effects"
+        );
+        let intermediate_camera = self.intermediate_camera(camera);
+        let mut color_index = 0;
+        let mut depth_index = 0;
+        for effect in self.effects.iter() {
+            let render_states = effect.render_states();
+            let (color_source, color_target) = ping_pong(&mut self.color_textures, color_index);
+            let (depth_source, depth_target) = ping_pong(&mut self.depth_textures, depth_index);
+            if render_states.blend != Blend::Disabled {
+                // The effect is blended with the content of the target, so start with a copy of the source
+                color_target.as_color_target(None).apply_screen_effect(
+                    &CopyEffect::default(),
+                    &intermediate_camera,
+                    &[],
+                    Some(ColorTexture::Single(color_source)),
+                    None,
+                );
+            }
+            if render_states.write_mask.depth {
+                RenderTarget::new(
+                    color_target.as_color_target(None),
+                    depth_target.as_depth_target(),
+                )
+                .clear(ClearState::depth(camera.depth_mapping.far_depth()))
+                .apply_screen_effect(
+                    effect.as_ref(),
+                    &intermediate_camera,
+                    lights,
+                    Some(ColorTexture::Single(color_source)),
+                    Some(DepthTexture::Single(depth_source)),
+                );
+                depth_index = 1 - depth_index;
+            } else {
+                color_target.as_color_target(None).apply_screen_effect(
+                    effect.as_ref(),
+                    &intermediate_camera,
+                    lights,
+                    Some(ColorTexture::Single(color_source)),
+                    Some(DepthTexture::Single(depth_source)),
+                );
+            }
+            color_index = 1 - color_index;
+        }
+        let color_texture = Some(ColorTexture::Single(&self.color_textures[color_index]));
+        let depth_texture = Some(DepthTexture::Single(&self.depth_textures[depth_index]));
+        if let Some(color_grading) = &self.color_grading {
+            target.apply_screen_effect(color_grading, camera, &[], color_texture, depth_texture);
+        } else {
+            target.apply_screen_effect(
+                &ScreenEffect::default(),
+                camera,
+                &[],
+                color_texture,
+                depth_texture,
+            );
+        }
+    }
+
+    fn intermediate_camera(&self, camera: &Camera) -> Camera {
+        let mut intermediate_camera = camera.clone();
+        intermediate_camera.disable_tone_and_color_mapping();
+        intermediate_camera.set_viewport(Viewport::new_at_origo(
+            camera.viewport().width,
+            camera.viewport().height,
+        ));
+        intermediate_camera
+    }
+}
+
+fn ping_pong<T>(textures: &mut [T], source_index: usize) -> (&T, &mut T) {
+    let (first, second) = textures.split_at_mut(1);
+    if source_index == 0 {
+        (&first[0], &mut second[0])
+    } else {
+        (&second[0], &mut first[0])
+    }
+}
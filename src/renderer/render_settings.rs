@@ -0,0 +1,74 @@
+use crate::renderer::*;
+
+///
+/// Settings controlling how objects are rendered in a render call, for example [RenderTarget::render_with_settings].
+///
+#[derive(Clone, Copy, Default)]
+pub struct RenderSettings<'a> {
+    /// The order in which the opaque objects are rendered.
+    /// The default is [RenderOrder::Distance].
+    pub order: RenderOrder,
+    /// An optional [OcclusionCuller] used to skip objects that are hidden behind other objects.
+    /// The same culler should be used for the same view every frame and the objects are identified by the [RenderSettings::object_ids].
+    /// The default is `None`, ie. only objects outside the camera frustum are culled.
+    pub occlusion_culler: Option<&'a OcclusionCuller>,
+    /// Ids identifying the objects given to the render call, one for each object in the same order as the objects.
+    /// The ids are used by the [RenderSettings::occlusion_culler] to keep track of the objects between frames,
+    /// so an object must have the same id every frame, whatever its position in the list of objects.
+    /// Only needed when using an occlusion culler. The default is an empty list.
+    pub object_ids: &'a [u64],
+    /// Whether or not to render the depth of the opaque forward rendered objects in a pre-pass using a [DepthMaterial]
+    /// and then shade the objects with [DepthTest::LessOrEqual] and depth writes disabled.
+    /// This way each pixel is shaded at most once which is beneficial when using expensive materials, for example [PhysicalMaterial], in scenes with a lot of overdraw.
+    /// Only objects that provide a material through [Object::with_geometry_and_material] without an alpha cutout (see [Material::has_alpha_cutout]) are part of the pre-pass.
+    /// The vertex shader of the geometries must declare `invariant gl_Position;` to make sure that the depth is the same in both passes, which is the case for the geometries in this crate.
+    /// The default is false.
+    pub depth_pre_pass: bool,
+    /// How the transparent objects are rendered.
+    /// The default is [TransparencyMode::Sorted].
+    pub transparency: TransparencyMode,
+}
+
+///
+/// Defines how transparent objects, ie. objects with a material of type [MaterialType::Transparent], are rendered in a render call.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransparencyMode {
+    /// Transparent objects are rendered from farthest away to closest to the camera using the [Blend] defined by their material.
+    /// The result is only correct if the objects do not intersect and the distance to the center of their bounding boxes gives the correct order.
+    #[default]
+    Sorted,
+    /// Weighted blended order-independent transparency, ie. transparent objects are accumulated into an accumulation and a revealage texture
+    /// weighted by their alpha value and distance to the camera, and then composited onto the render target.
+    /// The result does not depend on the order, so intersecting and large transparent objects are rendered without popping, but the result is an approximation.
+    /// The blend defined by the materials is ignored and only objects that provide a material through [Object::with_geometry_and_material]
+    /// and whose material [supports weighted blended transparency](Material::supports_weighted_blended) are order-independent,
+    /// which is the case for the [ColorMaterial], [PhysicalMaterial] and [ToonMaterial]. Other transparent objects are rendered afterwards, sorted from back to front.
+    WeightedBlended,
+}
+
+///
+/// Defines the order in which opaque objects are rendered in a render call.
+/// Transmissive and transparent objects are always rendered after the opaque objects from farthest away to closest to the camera.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderOrder {
+    /// Opaque objects are rendered from nearest to farthest away from the camera, see [cmp_render_order].
+    #[default]
+    Distance,
+    /// Opaque objects are grouped by their [StateKey] so that objects using the same shader program are rendered after each other,
+    /// thereby minimizing the number of program changes.
+    /// Within each group, the objects are rendered from nearest to farthest away from the camera, see [cmp_render_order_by_state].
+    State,
+}
+
+///
+/// Identifies the shader program used when rendering an [Object], see [Object::state_key].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StateKey {
+    /// The id of the vertex shader variation of the geometry, see [Geometry::id].
+    pub geometry_id: u16,
+    /// The id of the fragment shader variation of the material, see [Material::id].
+    pub material_id: u16,
+}
@@ -40,11 +40,11 @@ impl HeadlessContext {
         let cb = ContextBuilder::new();
         let glutin_context = build_context(cb)?;
         let glutin_context = unsafe { glutin_context.make_current().map_err(|(_, e)| e)? };
-        let context = Context::from_gl_context(std::sync::Arc::new(unsafe {
-            crate::context::Context::from_loader_function(|s| {
-                glutin_context.get_proc_address(s) as *const _
-            })
+        let get_proc_address = |s: &str| glutin_context.get_proc_address(s) as *const _;
+        let mut context = Context::from_gl_context(std::sync::Arc::new(unsafe {
+            crate::context::Context::from_loader_function(get_proc_address)
         }))?;
+        context.load_clip_control(get_proc_address);
         Ok(Self {
             context,
             _glutin_context: Rc::new(glutin_context),